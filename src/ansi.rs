@@ -0,0 +1,56 @@
+/// Removes escape sequences and carriage returns from terminal output, leaving
+/// the text as it would read in a log.
+pub(crate) fn strip(bytes: &[u8]) -> String {
+    let mut text = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            0x1b => i = skip_escape(bytes, i + 1),
+            b'\r' => i += 1,
+            byte => {
+                text.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&text).into_owned()
+}
+
+/// The index just past the escape sequence whose introducer is at `start`.
+fn skip_escape(bytes: &[u8], start: usize) -> usize {
+    match bytes.get(start) {
+        // CSI: parameters and intermediates up to a final byte.
+        Some(b'[') => bytes[start + 1..]
+            .iter()
+            .position(|b| (0x40..=0x7e).contains(b))
+            .map_or(bytes.len(), |end| start + 1 + end + 1),
+        // OSC, DCS and friends: up to BEL or ST.
+        Some(b']' | b'P' | b'X' | b'^' | b'_') => {
+            let mut i = start + 1;
+            while i < bytes.len() {
+                match bytes[i] {
+                    0x07 => return i + 1,
+                    0x1b if bytes.get(i + 1) == Some(&b'\\') => return i + 2,
+                    _ => i += 1,
+                }
+            }
+            bytes.len()
+        }
+        // Character set selection takes one more byte.
+        Some(b'(' | b')' | b'*' | b'+') => (start + 2).min(bytes.len()),
+        Some(_) => start + 1,
+        None => start,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_escape_sequences() {
+        assert_eq!("error: failed", strip(b"\x1b[1;31merror\x1b[0m: failed\r"));
+        assert_eq!("title gone", strip(b"\x1b]0;hawk\x07title \x1b(Bgone"));
+        assert_eq!("cut", strip(b"cut\x1b[3"));
+    }
+}
@@ -0,0 +1,27 @@
+//! Session recordings in the asciicast v2 format, as used by asciinema:
+//! <https://docs.asciinema.org/manual/asciicast/v2/>
+
+mod recorder;
+mod replay;
+
+pub(crate) use recorder::Recorder;
+pub(crate) use replay::replay;
+
+use serde::{Deserialize, Serialize};
+
+/// The first line of a recording.
+#[derive(Debug, Serialize, Deserialize)]
+struct Header {
+    version: u8,
+    width: u16,
+    height: u16,
+    /// When the recording started, in seconds since the epoch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    timestamp: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+}
+
+/// Every line after the header: seconds since the start, the kind of event
+/// ("o" for output, "r" for a resize to "COLSxROWS") and its data.
+type CastEvent = (f64, String, String);
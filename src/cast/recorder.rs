@@ -0,0 +1,121 @@
+use std::{
+    fs::{self, File},
+    io::Write,
+    path::Path,
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Context;
+
+use super::Header;
+
+/// Records one process's output, across restarts, to `<name>.cast`.
+#[derive(Debug)]
+pub(crate) struct Recorder {
+    name: String,
+    file: File,
+    /// When the header was written, which event times count from.
+    start: Option<Instant>,
+    /// The start of a character split across reads, since asciicast output is text.
+    partial: Vec<u8>,
+}
+
+impl Recorder {
+    pub(crate) fn create(dir: &Path, name: &str) -> anyhow::Result<Self> {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("Couldn't create recording directory {:?}", dir))?;
+        let path = dir.join(format!("{}.cast", name));
+        let file =
+            File::create(&path).with_context(|| format!("Couldn't create recording {:?}", path))?;
+        Ok(Self {
+            name: name.to_owned(),
+            file,
+            start: None,
+            partial: Vec::new(),
+        })
+    }
+
+    /// Writes the header for a screen of `rows` by `cols`, unless an earlier
+    /// run already has.
+    pub(crate) fn start(&mut self, rows: u16, cols: u16) {
+        if self.start.is_some() {
+            return;
+        }
+        let header = Header {
+            version: 2,
+            width: cols,
+            height: rows,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .ok()
+                .map(|time| time.as_secs()),
+            title: Some(self.name.clone()),
+        };
+        self.start = Some(Instant::now());
+        self.write_line(&header);
+    }
+
+    pub(crate) fn output(&mut self, output: &[u8]) {
+        self.partial.extend_from_slice(output);
+        // Keep an incomplete character at the end for the next read.
+        let complete = match std::str::from_utf8(&self.partial) {
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            _ => self.partial.len(),
+        };
+        let rest = self.partial.split_off(complete);
+        let text = String::from_utf8_lossy(&self.partial).into_owned();
+        self.partial = rest;
+        self.event("o", text);
+    }
+
+    pub(crate) fn resize(&mut self, rows: u16, cols: u16) {
+        self.event("r", format!("{}x{}", cols, rows));
+    }
+
+    fn event(&mut self, kind: &str, data: String) {
+        let Some(start) = self.start else {
+            return;
+        };
+        if data.is_empty() {
+            return;
+        }
+        let time = start.elapsed().as_secs_f64();
+        self.write_line(&(time, kind, data));
+    }
+
+    /// Recording is best effort, so errors are dropped rather than taking the
+    /// process down with them.
+    fn write_line<T: serde::Serialize>(&mut self, value: &T) {
+        if let Ok(mut line) = serde_json::to_vec(value) {
+            line.push(b'\n');
+            let _ = self.file.write_all(&line);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_split_characters_together() {
+        let dir = std::env::temp_dir().join(format!("hawk-cast-{}", std::process::id()));
+        let mut recorder = Recorder::create(&dir, "web").unwrap();
+        recorder.start(24, 80);
+        recorder.output("caf\u{e9}".as_bytes().split_last().unwrap().1);
+        recorder.output(&[0xa9, b'!']);
+        recorder.resize(10, 40);
+
+        let cast = fs::read_to_string(dir.join("web.cast")).unwrap();
+        let lines: Vec<serde_json::Value> = cast
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines[0]["width"], 80);
+        assert_eq!(lines[1][2], "caf");
+        assert_eq!(lines[2][2], "\u{e9}!");
+        assert_eq!(lines[3][1], "r");
+        assert_eq!(lines[3][2], "40x10");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
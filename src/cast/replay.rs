@@ -0,0 +1,187 @@
+use std::{
+    fs,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use anyhow::Context;
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Paragraph},
+    Terminal,
+};
+use tui_term::widget::PseudoTerminal;
+
+use super::{CastEvent, Header};
+use crate::terminal;
+
+/// How far Right skips ahead.
+const SKIP: Duration = Duration::from_secs(5);
+
+/// Plays the recording at `path` back in a pane, `speed` times as fast as it
+/// was recorded.
+pub(crate) fn replay(path: &Path, speed: f64) -> anyhow::Result<u8> {
+    let mut player = Player::load(path, speed)?;
+    let mut terminal = terminal::setup_terminal()?;
+    let result = player.run(&mut terminal);
+    terminal::cleanup_terminal(terminal)?;
+    result.map(|()| 0)
+}
+
+struct Player {
+    header: Header,
+    events: Vec<CastEvent>,
+    /// The next event to play.
+    next: usize,
+    /// How far into the recording playback has got, in seconds.
+    position: f64,
+    speed: f64,
+    paused: bool,
+    parser: vt100::Parser,
+}
+
+impl Player {
+    fn load(path: &Path, speed: f64) -> anyhow::Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Couldn't read recording {:?}", path))?;
+        let mut lines = content
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.is_empty());
+        let (_, header) = lines.next().context("The recording is empty")?;
+        let header: Header =
+            serde_json::from_str(header).context("Invalid asciicast header on line 1")?;
+        if header.version != 2 {
+            anyhow::bail!(
+                "Unsupported asciicast version {}, expected 2",
+                header.version
+            );
+        }
+        let events = lines
+            .map(|(i, line)| {
+                serde_json::from_str(line)
+                    .with_context(|| format!("Invalid asciicast event on line {}", i + 1))
+            })
+            .collect::<anyhow::Result<_>>()?;
+        let parser = vt100::Parser::new(header.height, header.width, 0);
+        Ok(Self {
+            header,
+            events,
+            next: 0,
+            position: 0.0,
+            speed,
+            paused: false,
+            parser,
+        })
+    }
+
+    fn run<B: Backend>(&mut self, terminal: &mut Terminal<B>) -> anyhow::Result<()> {
+        let mut last = Instant::now();
+        loop {
+            terminal.draw(|f| self.render(f.area(), f.buffer_mut()))?;
+
+            if event::poll(Duration::from_millis(10))? {
+                if let Event::Key(key) = event::read()? {
+                    if key.kind == KeyEventKind::Press && !self.handle_key(key.code) {
+                        return Ok(());
+                    }
+                }
+            }
+            let now = Instant::now();
+            if !self.paused {
+                self.advance((now - last).as_secs_f64() * self.speed);
+            }
+            last = now;
+        }
+    }
+
+    /// Handles a key press, returning `false` once playback should stop.
+    fn handle_key(&mut self, code: KeyCode) -> bool {
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Char(' ') => self.paused = !self.paused,
+            KeyCode::Right => self.advance(SKIP.as_secs_f64()),
+            KeyCode::Char('+') => self.speed *= 2.0,
+            KeyCode::Char('-') => self.speed /= 2.0,
+            _ => {}
+        }
+        true
+    }
+
+    /// Moves playback `seconds` further on, playing the events along the way.
+    fn advance(&mut self, seconds: f64) {
+        self.position = (self.position + seconds).min(self.duration());
+        while let Some((time, kind, data)) = self.events.get(self.next) {
+            if *time > self.position {
+                break;
+            }
+            match kind.as_str() {
+                "o" => self.parser.process(data.as_bytes()),
+                "r" => {
+                    if let Some((cols, rows)) = data
+                        .split_once('x')
+                        .and_then(|(cols, rows)| Some((cols.parse().ok()?, rows.parse().ok()?)))
+                    {
+                        self.parser.set_size(rows, cols);
+                    }
+                }
+                // Input and markers don't change the screen.
+                _ => {}
+            }
+            self.next += 1;
+        }
+    }
+
+    fn duration(&self) -> f64 {
+        self.events.last().map_or(0.0, |(time, _, _)| *time)
+    }
+
+    fn title(&self) -> String {
+        let name = self.header.title.as_deref().unwrap_or("recording");
+        let state = if self.next == self.events.len() {
+            " [finished]"
+        } else if self.paused {
+            " [paused]"
+        } else {
+            ""
+        };
+        format!(
+            "{} (replay {:.1}s of {:.1}s at {}x){}",
+            name,
+            self.position,
+            self.duration(),
+            self.speed,
+            state
+        )
+    }
+
+    fn render(&self, area: Rect, buf: &mut Buffer) {
+        let [header, main, footer] = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(1),
+                Constraint::Fill(1),
+                Constraint::Length(1),
+            ])
+            .areas(area);
+
+        Paragraph::new(self.title())
+            .style(Style::default().add_modifier(Modifier::BOLD))
+            .alignment(Alignment::Center)
+            .render(header, buf);
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan))
+            .style(Style::default().add_modifier(Modifier::BOLD));
+        PseudoTerminal::new(self.parser.screen())
+            .block(block)
+            .render(main, buf);
+
+        Paragraph::new("REPLAY  (space pause, Right skip 5s, +/- speed, q quit)")
+            .style(Style::default().add_modifier(Modifier::BOLD | Modifier::REVERSED))
+            .alignment(Alignment::Center)
+            .render(footer, buf);
+    }
+}
@@ -0,0 +1,70 @@
+use std::io::{self, Write};
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Puts `text` on the system clipboard of whichever terminal hawk is displayed
+/// in, via OSC 52, so it works over SSH without any clipboard tool installed.
+pub(crate) fn copy(text: &str) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    stdout.write_all(&osc52(text, std::env::var_os("TMUX").is_some()))?;
+    stdout.flush()
+}
+
+fn osc52(text: &str, tmux: bool) -> Vec<u8> {
+    let sequence = format!("\x1b]52;c;{}\x07", base64(text.as_bytes()));
+    if tmux {
+        // tmux only passes the sequence on to the outer terminal when it's wrapped
+        // in a DCS passthrough, with every escape doubled.
+        format!("\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b")).into_bytes()
+    } else {
+        sequence.into_bytes()
+    }
+}
+
+fn base64(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let indices = [
+            b[0] >> 2,
+            (b[0] & 0b11) << 4 | b[1] >> 4,
+            (b[1] & 0b1111) << 2 | b[2] >> 6,
+            b[2] & 0b11_1111,
+        ];
+        for (i, index) in indices.iter().enumerate() {
+            if i <= chunk.len() {
+                encoded.push(char::from(BASE64[usize::from(*index)]));
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_base64_with_padding() {
+        assert_eq!("", base64(b""));
+        assert_eq!("Zg==", base64(b"f"));
+        assert_eq!("Zm8=", base64(b"fo"));
+        assert_eq!("Zm9v", base64(b"foo"));
+        assert_eq!("aGF3ayB0dWk=", base64(b"hawk tui"));
+    }
+
+    #[test]
+    fn wraps_osc52_for_tmux() {
+        assert_eq!(b"\x1b]52;c;aGk=\x07".to_vec(), osc52("hi", false));
+        assert_eq!(
+            b"\x1bPtmux;\x1b\x1b]52;c;aGk=\x07\x1b\\".to_vec(),
+            osc52("hi", true)
+        );
+    }
+}
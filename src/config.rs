@@ -0,0 +1,108 @@
+mod highlight;
+mod layout;
+mod limits;
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use serde::Deserialize;
+
+pub(crate) use highlight::HighlightRule;
+pub(crate) use layout::{LayoutConfig, Part, PartContent};
+pub(crate) use limits::Limits;
+
+/// The settings file, `hawk.toml` beside the Procfile by default:
+///
+/// ```toml
+/// # Styles applied to output matching a regex. Rules marked as errors count
+/// # towards the badge on panes that aren't focused.
+/// [[highlight]]
+/// pattern = "(?i)error|panic"
+/// style = "red bold"
+/// error = true
+///
+/// [[highlight]]
+/// pattern = "(?i)warn"
+/// style = "black on yellow"
+///
+/// # Where the panes go: api and worker stacked on the left at 60%, and the
+/// # rest on the right. Sizes are a percentage, a fraction like 1/3, or a number
+/// # of rows or columns, and parts share what's left evenly without one. Parts
+/// # are stacked vertically unless their direction is "horizontal". Processes
+/// # that are hidden or left out get no pane, and without parts every process
+/// # that isn't hidden is tiled in a grid.
+/// [layout]
+/// hidden = ["db"]
+/// direction = "horizontal"
+/// parts = [
+///     { size = "60%", parts = [{ process = "api" }, { process = "worker" }] },
+///     { parts = [{ process = "db", size = "5" }, { process = "redis" }] },
+/// ]
+///
+/// # Resources a process may use: resident memory summed over its descendants,
+/// # checked each time usage is sampled, CPU time in seconds and open files.
+/// # Going over the memory or CPU limit kills it.
+/// [limits.worker]
+/// memory = "2G"
+/// cpu = 600
+/// files = 1024
+/// ```
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Config {
+    #[serde(default = "HighlightRule::defaults")]
+    pub(crate) highlight: Vec<HighlightRule>,
+    #[serde(default)]
+    pub(crate) layout: LayoutConfig,
+    /// Limits by process name.
+    #[serde(default)]
+    pub(crate) limits: HashMap<String, Limits>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            highlight: HighlightRule::defaults(),
+            layout: LayoutConfig::default(),
+            limits: HashMap::new(),
+        }
+    }
+}
+
+impl Config {
+    /// Loads the settings from `path`, or from `hawk.toml` beside `procfile` if
+    /// it exists, falling back to the defaults.
+    pub(crate) fn load(path: Option<&Path>, procfile: &Path) -> anyhow::Result<Self> {
+        // Only the default location is optional; a path that was asked for must exist.
+        let Some(path) = path
+            .map(Path::to_path_buf)
+            .or_else(|| Some(default_path(procfile)).filter(|path| path.exists()))
+        else {
+            return Ok(Self::default());
+        };
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed reading settings {:?}", path))?;
+        toml::from_str(&content).with_context(|| format!("Invalid settings {:?}", path))
+    }
+}
+
+fn default_path(procfile: &Path) -> PathBuf {
+    procfile.with_file_name("hawk.toml")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requires_a_given_settings_file() {
+        let procfile = Path::new("/nonexistent/Procfile");
+        assert!(Config::load(None, procfile).is_ok());
+        let missing = Path::new("/nonexistent/hawk.toml");
+        let error = Config::load(Some(missing), procfile).unwrap_err();
+        assert!(error.to_string().contains("Failed reading settings"));
+    }
+}
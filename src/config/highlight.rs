@@ -0,0 +1,111 @@
+use ratatui::style::{Color, Modifier, Style};
+use regex::Regex;
+use serde::Deserialize;
+
+/// Styles output matching `pattern` when it's rendered.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "RuleFile")]
+pub(crate) struct HighlightRule {
+    pub(crate) pattern: Regex,
+    pub(crate) style: Style,
+    /// Whether a match counts towards a pane's error badge.
+    pub(crate) error: bool,
+}
+
+/// A highlight rule as written in the settings file.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleFile {
+    pattern: String,
+    style: String,
+    #[serde(default)]
+    error: bool,
+}
+
+impl TryFrom<RuleFile> for HighlightRule {
+    type Error = String;
+
+    fn try_from(rule: RuleFile) -> Result<Self, Self::Error> {
+        Ok(Self {
+            pattern: Regex::new(&rule.pattern).map_err(|e| e.to_string())?,
+            style: parse_style(&rule.style)?,
+            error: rule.error,
+        })
+    }
+}
+
+impl HighlightRule {
+    /// Errors in red and warnings in yellow, unless the settings say otherwise.
+    pub(crate) fn defaults() -> Vec<Self> {
+        [
+            ("(?i)error|panic", "red bold", true),
+            ("(?i)warn", "yellow", false),
+        ]
+        .into_iter()
+        .filter_map(|(pattern, style, error)| {
+            Self::try_from(RuleFile {
+                pattern: pattern.to_owned(),
+                style: style.to_owned(),
+                error,
+            })
+            .ok()
+        })
+        .collect()
+    }
+}
+
+/// Parses a style like "bold red on black": colours and modifiers in any order,
+/// with the colour after "on" being the background.
+fn parse_style(value: &str) -> Result<Style, String> {
+    let mut style = Style::default();
+    let mut words = value.split_whitespace();
+    while let Some(word) = words.next() {
+        style = match word {
+            "bold" => style.add_modifier(Modifier::BOLD),
+            "dim" => style.add_modifier(Modifier::DIM),
+            "italic" => style.add_modifier(Modifier::ITALIC),
+            "underlined" => style.add_modifier(Modifier::UNDERLINED),
+            "reversed" => style.add_modifier(Modifier::REVERSED),
+            "on" => {
+                let color = words
+                    .next()
+                    .ok_or_else(|| format!("expected a colour after 'on' in '{}'", value))?;
+                style.bg(parse_color(color)?)
+            }
+            color => style.fg(parse_color(color)?),
+        };
+    }
+    Ok(style)
+}
+
+fn parse_color(value: &str) -> Result<Color, String> {
+    value.parse().map_err(|_| {
+        format!(
+            "unknown colour or modifier '{}', expected e.g. red, light-blue, #ff8800, bold, dim, italic, underlined or reversed",
+            value
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_styles() {
+        assert_eq!(
+            parse_style("bold red on black"),
+            Ok(Style::default()
+                .fg(Color::Red)
+                .bg(Color::Black)
+                .add_modifier(Modifier::BOLD))
+        );
+        assert_eq!(
+            parse_style("#ff8800"),
+            Ok(Style::default().fg(Color::Rgb(0xff, 0x88, 0x00)))
+        );
+        assert!(parse_style("reed").is_err());
+        assert!(parse_style("red on").is_err());
+        assert_eq!(HighlightRule::defaults().len(), 2);
+    }
+}
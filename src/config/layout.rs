@@ -0,0 +1,175 @@
+use ratatui::layout::{Constraint, Direction};
+use serde::Deserialize;
+
+/// Where each process's pane goes, as a tree of splits.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct LayoutConfig {
+    /// Processes that don't get a pane until they're shown.
+    #[serde(default)]
+    pub(crate) hidden: Vec<String>,
+    #[serde(default)]
+    pub(crate) direction: SplitDirection,
+    /// The parts the screen is split into, or none to tile every process in a grid.
+    #[serde(default)]
+    pub(crate) parts: Vec<Part>,
+}
+
+impl LayoutConfig {
+    /// Whether the layout gives `name` a pane, which it does for every process
+    /// when there's no tree of splits.
+    pub(crate) fn places(&self, name: &str) -> bool {
+        self.parts.is_empty() || self.parts.iter().any(|part| part.contains(name))
+    }
+
+    /// Every process named in the parts.
+    pub(crate) fn placed(&self) -> Vec<&str> {
+        let mut names = Vec::new();
+        for part in &self.parts {
+            part.collect(&mut names);
+        }
+        names
+    }
+}
+
+/// Whether a split's parts sit above each other or side by side.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum SplitDirection {
+    #[default]
+    Vertical,
+    Horizontal,
+}
+
+impl From<SplitDirection> for Direction {
+    fn from(direction: SplitDirection) -> Self {
+        match direction {
+            SplitDirection::Vertical => Self::Vertical,
+            SplitDirection::Horizontal => Self::Horizontal,
+        }
+    }
+}
+
+/// One part of a split: a process's pane, or a further split.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "PartFile")]
+pub(crate) struct Part {
+    pub(crate) size: Constraint,
+    pub(crate) content: PartContent,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum PartContent {
+    Process(String),
+    Split {
+        direction: SplitDirection,
+        parts: Vec<Part>,
+    },
+}
+
+impl Part {
+    fn contains(&self, name: &str) -> bool {
+        match &self.content {
+            PartContent::Process(process) => process == name,
+            PartContent::Split { parts, .. } => parts.iter().any(|part| part.contains(name)),
+        }
+    }
+
+    fn collect<'a>(&'a self, names: &mut Vec<&'a str>) {
+        match &self.content {
+            PartContent::Process(process) => names.push(process),
+            PartContent::Split { parts, .. } => {
+                for part in parts {
+                    part.collect(names);
+                }
+            }
+        }
+    }
+}
+
+/// A part as written in the settings file.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PartFile {
+    size: Option<String>,
+    process: Option<String>,
+    #[serde(default)]
+    direction: SplitDirection,
+    #[serde(default)]
+    parts: Vec<Part>,
+}
+
+impl TryFrom<PartFile> for Part {
+    type Error = String;
+
+    fn try_from(part: PartFile) -> Result<Self, Self::Error> {
+        let size = part
+            .size
+            .as_deref()
+            .map_or(Ok(Constraint::Fill(1)), parse_size)?;
+        let content = match (part.process, part.parts.is_empty()) {
+            (Some(process), true) => PartContent::Process(process),
+            (None, false) => PartContent::Split {
+                direction: part.direction,
+                parts: part.parts,
+            },
+            _ => return Err("each part needs either a process or parts, not both".to_owned()),
+        };
+        Ok(Self { size, content })
+    }
+}
+
+/// Parses a size like "60%", "1/3" of the space, or "20" rows or columns.
+fn parse_size(value: &str) -> Result<Constraint, String> {
+    let invalid = || {
+        format!(
+            "invalid size '{}', expected e.g. 60%, 1/3 or 20 (rows or columns)",
+            value
+        )
+    };
+    let number = |text: &str| text.trim().parse().map_err(|_| invalid());
+    if let Some(percent) = value.strip_suffix('%') {
+        Ok(Constraint::Percentage(number(percent)?))
+    } else if let Some((numerator, denominator)) = value.split_once('/') {
+        Ok(Constraint::Ratio(
+            number(numerator)?.into(),
+            number(denominator)?.into(),
+        ))
+    } else {
+        Ok(Constraint::Length(number(value)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_sizes() {
+        assert_eq!(parse_size("60%"), Ok(Constraint::Percentage(60)));
+        assert_eq!(parse_size("1/3"), Ok(Constraint::Ratio(1, 3)));
+        assert_eq!(parse_size("20"), Ok(Constraint::Length(20)));
+        assert!(parse_size("big").is_err());
+        assert!(parse_size("1/x").is_err());
+    }
+
+    #[test]
+    fn lists_placed_processes() {
+        let part = |content| Part {
+            size: Constraint::Fill(1),
+            content,
+        };
+        let process = |name: &str| part(PartContent::Process(name.to_owned()));
+        let layout = LayoutConfig {
+            parts: vec![
+                process("api"),
+                part(PartContent::Split {
+                    direction: SplitDirection::Horizontal,
+                    parts: vec![process("db"), process("redis")],
+                }),
+            ],
+            ..LayoutConfig::default()
+        };
+        assert_eq!(layout.placed(), ["api", "db", "redis"]);
+    }
+}
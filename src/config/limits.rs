@@ -0,0 +1,87 @@
+use serde::Deserialize;
+
+/// Seconds of CPU time a process gets after its limit before it's killed outright.
+const CPU_GRACE: u64 = 5;
+
+/// Resources a process may use before it's stopped.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(try_from = "LimitsFile")]
+pub(crate) struct Limits {
+    /// Resident memory in bytes, summed over the process and its descendants.
+    pub(crate) memory: Option<u64>,
+    /// CPU time in seconds.
+    pub(crate) cpu: Option<u64>,
+    /// Open files.
+    pub(crate) files: Option<u64>,
+}
+
+/// Limits as written in the settings file.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LimitsFile {
+    memory: Option<String>,
+    cpu: Option<u64>,
+    files: Option<u64>,
+}
+
+impl TryFrom<LimitsFile> for Limits {
+    type Error = String;
+
+    fn try_from(limits: LimitsFile) -> Result<Self, Self::Error> {
+        Ok(Self {
+            memory: limits
+                .memory
+                .as_deref()
+                .map(crate::parse_size)
+                .transpose()?,
+            cpu: limits.cpu,
+            files: limits.files,
+        })
+    }
+}
+
+impl Limits {
+    /// A shell script that sets the limits the kernel enforces, then runs the
+    /// command in its arguments, or `None` if there aren't any. Memory isn't one
+    /// of them, since Linux ignores the limit on resident memory.
+    ///
+    /// portable-pty has no hook to run code in the child before it execs, and
+    /// `setrlimit` there would need unsafe code, so a shell sets the limits
+    /// instead. It execs the command, which keeps the pid hawk watches and
+    /// passes the limits on to everything the command starts.
+    pub(crate) fn ulimit_script(&self) -> Option<String> {
+        // Some shells only take one limit per ulimit. Going over the soft CPU
+        // limit raises SIGXCPU, which is what's reported, and the hard one
+        // kills anything that ignores it.
+        let mut commands = Vec::new();
+        if let Some(cpu) = self.cpu {
+            commands.push(format!("ulimit -S -t {}", cpu));
+            commands.push(format!("ulimit -H -t {}", cpu + CPU_GRACE));
+        }
+        if let Some(files) = self.files {
+            commands.push(format!("ulimit -n {}", files));
+        }
+        (!commands.is_empty()).then(|| {
+            commands.push("exec \"$@\"".to_owned());
+            commands.join(" && ")
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_ulimit_scripts() {
+        let limits: Limits = toml::from_str("memory = \"512M\"\ncpu = 60\nfiles = 256").unwrap();
+        assert_eq!(limits.memory, Some(512 * 1024 * 1024));
+        assert_eq!(
+            limits.ulimit_script().as_deref(),
+            Some("ulimit -S -t 60 && ulimit -H -t 65 && ulimit -n 256 && exec \"$@\"")
+        );
+        let limits: Limits = toml::from_str("memory = \"1G\"").unwrap();
+        assert_eq!(limits.ulimit_script(), None);
+        assert!(toml::from_str::<Limits>("memory = \"lots\"").is_err());
+    }
+}
@@ -0,0 +1,23 @@
+mod client;
+mod protocol;
+mod server;
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+
+pub(crate) use client::{attach, send};
+pub(crate) use protocol::Request;
+pub(crate) use server::Server;
+
+/// The control socket for the hawk running `procfile`, which lives next to it.
+pub(crate) fn socket_path(procfile: &Path) -> anyhow::Result<PathBuf> {
+    let dir = procfile
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+    let dir = dir
+        .canonicalize()
+        .with_context(|| format!("Couldn't find the directory of {:?}", procfile))?;
+    Ok(dir.join(".hawk.sock"))
+}
@@ -0,0 +1,162 @@
+use std::{path::Path, time::Duration};
+
+use anyhow::Context;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{
+        unix::{OwnedReadHalf, OwnedWriteHalf},
+        UnixStream,
+    },
+};
+
+use super::protocol::{Request, Response};
+use crate::process::format_duration;
+
+/// Sends `request` to the hawk listening on `path` and prints its reply, as JSON
+/// or for people. Returns the code `hawk ctl` should exit with.
+pub(crate) async fn send(path: &Path, request: &Request, json: bool) -> anyhow::Result<u8> {
+    let stream = UnixStream::connect(path)
+        .await
+        .with_context(|| format!("Couldn't connect to hawk at {:?}; is it running?", path))?;
+    let (reader, mut writer) = stream.into_split();
+
+    let mut line = serde_json::to_vec(request)?;
+    line.push(b'\n');
+    writer.write_all(&line).await?;
+
+    let mut lines = BufReader::new(reader).lines();
+    let reply = lines
+        .next_line()
+        .await?
+        .context("hawk closed the connection without replying")?;
+    let response: Response = serde_json::from_str(&reply)?;
+
+    if *request == Request::Events && response.ok {
+        // Events are already JSON, so they're printed as they come either way.
+        while let Some(event) = lines.next_line().await? {
+            println!("{}", event);
+        }
+        return Ok(0);
+    }
+    if json {
+        println!("{}", reply);
+    } else if let Some(error) = &response.error {
+        eprintln!("Error: {}", error);
+    } else {
+        print_processes(&response);
+    }
+    Ok(u8::from(!response.ok))
+}
+
+/// Ctrl-], which detaches from a process when sending it input.
+const DETACH: u8 = 0x1d;
+
+/// Streams the output of the process called `name` to the terminal, sending it
+/// keystrokes too if `input` is set, until Ctrl-] (or Ctrl-C without input).
+pub(crate) async fn attach(path: &Path, name: &str, input: bool) -> anyhow::Result<u8> {
+    let stream = UnixStream::connect(path)
+        .await
+        .with_context(|| format!("Couldn't connect to hawk at {:?}; is it running?", path))?;
+    let (reader, mut writer) = stream.into_split();
+
+    let request = Request::Attach {
+        name: name.to_owned(),
+        input,
+    };
+    let mut line = serde_json::to_vec(&request)?;
+    line.push(b'\n');
+    writer.write_all(&line).await?;
+
+    let mut reader = BufReader::new(reader);
+    let mut reply = String::new();
+    reader.read_line(&mut reply).await?;
+    let response: Response =
+        serde_json::from_str(&reply).context("hawk closed the connection without replying")?;
+    if let Some(error) = response.error {
+        eprintln!("Error: {}", error);
+        return Ok(1);
+    }
+
+    if input {
+        crossterm::terminal::enable_raw_mode()?;
+    }
+    let result = stream_output(&mut reader, &mut writer, input).await;
+    if input {
+        crossterm::terminal::disable_raw_mode()?;
+    }
+    println!("\r\n[detached from {}]", name);
+    result.map(|()| 0)
+}
+
+async fn stream_output(
+    reader: &mut BufReader<OwnedReadHalf>,
+    writer: &mut OwnedWriteHalf,
+    input: bool,
+) -> anyhow::Result<()> {
+    let mut stdin = tokio::io::stdin();
+    let mut stdout = tokio::io::stdout();
+    let mut output = [0u8; 8192];
+    let mut keys = [0u8; 1024];
+    loop {
+        tokio::select! {
+            read = reader.read(&mut output) => match read? {
+                0 => return Ok(()),
+                size => {
+                    stdout.write_all(&output[..size]).await?;
+                    stdout.flush().await?;
+                }
+            },
+            read = stdin.read(&mut keys), if input => match read? {
+                0 => return Ok(()),
+                size => {
+                    let keys = &keys[..size];
+                    if let Some(end) = keys.iter().position(|key| *key == DETACH) {
+                        writer.write_all(&keys[..end]).await?;
+                        return Ok(());
+                    }
+                    writer.write_all(keys).await?;
+                }
+            },
+        }
+    }
+}
+
+fn print_processes(response: &Response) {
+    let width = response
+        .processes
+        .iter()
+        .map(|process| process.name.len())
+        .max()
+        .unwrap_or(0);
+    for process in &response.processes {
+        let mut line = format!("{:width$}  {:8}", process.name, process.state);
+        if let Some(pid) = process.pid {
+            line.push_str(&format!("  pid {}", pid));
+        }
+        if let Some(exit_status) = &process.exit_status {
+            line.push_str(&format!("  {}", exit_status));
+        }
+        if let Some(uptime) = process.uptime {
+            line.push_str(&format!(
+                "  up {}",
+                format_duration(Duration::from_secs(uptime))
+            ));
+        }
+        match process.restarts {
+            Some(1) => line.push_str("  1 restart"),
+            Some(restarts) if restarts > 1 => line.push_str(&format!("  {} restarts", restarts)),
+            _ => {}
+        }
+        println!("{}", line.trim_end());
+        for run in &process.runs {
+            println!(
+                "{:width$}    {}  {:6}  ran {}  {}",
+                "",
+                run.started,
+                run.started_by,
+                format_duration(Duration::from_secs(run.duration)),
+                run.ended
+            );
+        }
+    }
+}
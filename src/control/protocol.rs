@@ -0,0 +1,117 @@
+use clap::Subcommand;
+use serde::{Deserialize, Serialize};
+
+/// A command sent to hawk over the control socket, one JSON object per line,
+/// e.g. `{"command":"restart","name":"web"}`.
+#[derive(Debug, Clone, PartialEq, Eq, Subcommand, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "kebab-case")]
+pub(crate) enum Request {
+    /// List the processes and whether they're running
+    List,
+    /// Show the details of every process, or just one
+    Status { name: Option<String> },
+    /// Restart a process, killing it first if it's running
+    Restart { name: String },
+    /// Stop a process until it's started again
+    Stop { name: String },
+    /// Start a process that has exited or been stopped
+    Start { name: String },
+    /// Stream process lifecycle events as lines of JSON until interrupted
+    Events,
+    /// Stream a process's raw output, and optionally send it input. After the
+    /// reply the connection carries raw bytes in both directions.
+    #[command(skip)]
+    Attach { name: String, input: bool },
+}
+
+/// hawk's reply to a request, also a single line of JSON.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct Response {
+    pub(crate) ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) error: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) processes: Vec<ProcessInfo>,
+}
+
+impl Response {
+    pub(crate) fn ok(processes: Vec<ProcessInfo>) -> Self {
+        Self {
+            ok: true,
+            error: None,
+            processes,
+        }
+    }
+
+    pub(crate) fn error(error: String) -> Self {
+        Self {
+            ok: false,
+            error: Some(error),
+            processes: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct ProcessInfo {
+    pub(crate) name: String,
+    /// running, exited, killed or stopped.
+    pub(crate) state: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) pid: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) exit_code: Option<u32>,
+    /// How the process exited, such as "Exited with code 1".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) exit_status: Option<String>,
+    /// When the current run started, such as "2024-05-01T12:34:56.789Z".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) started: Option<String>,
+    /// What started the current run: "launch" along with hawk, "key" for a
+    /// restart from the keyboard, or "ctl" through the control socket.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) started_by: Option<String>,
+    /// Seconds the current run has been going, while it's running.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) uptime: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) restarts: Option<usize>,
+    /// The most recent runs before the current one, oldest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) runs: Vec<RunInfo>,
+}
+
+/// A previous run of a process.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct RunInfo {
+    pub(crate) started: String,
+    /// What started it, like [`ProcessInfo::started_by`].
+    pub(crate) started_by: String,
+    /// Seconds it ran for.
+    pub(crate) duration: u64,
+    /// How it ended, such as "Exited with code 1", or "killed" if it was running.
+    pub(crate) ended: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requests_are_tagged_by_command() {
+        assert_eq!(
+            Request::Restart {
+                name: "web".to_owned()
+            },
+            serde_json::from_str(r#"{"command":"restart","name":"web"}"#).unwrap()
+        );
+        assert_eq!(
+            Request::Status { name: None },
+            serde_json::from_str(r#"{"command":"status"}"#).unwrap()
+        );
+        assert_eq!(
+            r#"{"command":"list"}"#,
+            serde_json::to_string(&Request::List).unwrap()
+        );
+    }
+}
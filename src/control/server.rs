@@ -0,0 +1,293 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use bytes::Bytes;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{
+        unix::{OwnedReadHalf, OwnedWriteHalf},
+        UnixListener, UnixStream,
+    },
+    sync::broadcast::error::RecvError,
+    task::JoinHandle,
+};
+
+use super::protocol::{ProcessInfo, Request, Response, RunInfo};
+use crate::{
+    events::EventBus,
+    logs,
+    process::{ProcessGroup, ProcessScreen, Run, StartedBy},
+};
+
+/// Listens on the control socket until dropped, when the socket is removed.
+#[derive(Debug)]
+pub(crate) struct Server {
+    path: PathBuf,
+    task: JoinHandle<()>,
+}
+
+impl Server {
+    /// Listens on `path`, unless another hawk already is. Without a socket
+    /// hawk still runs, just out of reach of `hawk ctl`, so that only warns.
+    pub(crate) fn start(
+        path: &Path,
+        group: ProcessGroup,
+        events: EventBus,
+    ) -> anyhow::Result<Option<Self>> {
+        if std::os::unix::net::UnixStream::connect(path).is_ok() {
+            anyhow::bail!("hawk is already running here (socket {:?})", path);
+        }
+        let listener = match Self::bind(path) {
+            Ok(listener) => listener,
+            Err(e) => {
+                eprintln!(
+                    "Warning: running without a control socket, since {:?} couldn't be created: {}",
+                    path, e
+                );
+                return Ok(None);
+            }
+        };
+        let task = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let group = group.clone();
+                let events = events.clone();
+                tokio::spawn(async move {
+                    // A client going away mid-request isn't hawk's problem.
+                    let _ = Self::serve(stream, &group, &events).await;
+                });
+            }
+        });
+        Ok(Some(Self {
+            path: path.to_owned(),
+            task,
+        }))
+    }
+
+    fn bind(path: &Path) -> io::Result<UnixListener> {
+        if path.exists() {
+            // Left behind by a hawk that didn't shut down cleanly.
+            std::fs::remove_file(path)?;
+        }
+        UnixListener::bind(path)
+    }
+
+    async fn serve(stream: UnixStream, group: &ProcessGroup, events: &EventBus) -> io::Result<()> {
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = BufReader::new(reader).lines();
+        while let Some(line) = lines.next_line().await? {
+            let response = match serde_json::from_str(&line) {
+                Ok(Request::Attach { name, input }) => {
+                    let reader = lines.into_inner();
+                    return Self::attach(group, &name, input, reader, writer).await;
+                }
+                Ok(Request::Events) => return Self::stream_events(events, writer).await,
+                Ok(Request::Restart { name }) => Self::restart(group, &name).await,
+                Ok(Request::Stop { name }) => Self::stop(group, &name).await,
+                Ok(request) => Self::handle(group, request),
+                Err(e) => Response::error(format!("invalid request: {}", e)),
+            };
+            Self::reply(&mut writer, &response).await?;
+        }
+        Ok(())
+    }
+
+    async fn reply(writer: &mut OwnedWriteHalf, response: &Response) -> io::Result<()> {
+        let mut json = serde_json::to_vec(response)?;
+        json.push(b'\n');
+        writer.write_all(&json).await
+    }
+
+    /// Sends every event from now on, one per line, until the client goes away.
+    async fn stream_events(events: &EventBus, mut writer: OwnedWriteHalf) -> io::Result<()> {
+        let mut events = events.subscribe();
+        Self::reply(&mut writer, &Response::ok(Vec::new())).await?;
+        loop {
+            match events.recv().await {
+                Ok(event) => writer.write_all(format!("{}\n", event).as_bytes()).await?,
+                Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => return Ok(()),
+            }
+        }
+    }
+
+    /// Streams the output of the process called `name` until the client goes
+    /// away, passing on whatever it sends if `input` is set.
+    async fn attach(
+        group: &ProcessGroup,
+        name: &str,
+        input: bool,
+        mut reader: BufReader<OwnedReadHalf>,
+        mut writer: OwnedWriteHalf,
+    ) -> io::Result<()> {
+        let (snapshot, mut output) =
+            match group.with_process(name, |process| Ok(process.subscribe())) {
+                Ok(subscription) => subscription,
+                Err(e) => return Self::reply(&mut writer, &Response::error(e.to_string())).await,
+            };
+        Self::reply(&mut writer, &Response::ok(Vec::new())).await?;
+        writer.write_all(&snapshot).await?;
+
+        let mut buf = [0u8; 1024];
+        loop {
+            tokio::select! {
+                chunk = output.recv() => match chunk {
+                    Ok(chunk) => writer.write_all(&chunk).await?,
+                    // Too slow to keep up; the screen catches up with later output.
+                    Err(RecvError::Lagged(_)) => {}
+                    Err(RecvError::Closed) => return Ok(()),
+                },
+                read = reader.read(&mut buf) => match read? {
+                    0 => return Ok(()),
+                    size if input => {
+                        let bytes = Bytes::copy_from_slice(&buf[..size]);
+                        // The process may have been restarted, so look it up each time.
+                        let _ = group.with_process(name, |process| {
+                            process.handle_input(bytes);
+                            Ok(())
+                        });
+                    }
+                    _ => {}
+                },
+            }
+        }
+    }
+
+    fn handle(group: &ProcessGroup, request: Request) -> Response {
+        let result = match request {
+            Request::List => Ok(group.processes(|process| ProcessInfo {
+                name: process.name.clone(),
+                state: process.status().state().to_owned(),
+                ..ProcessInfo::default()
+            })),
+            Request::Status { name } => {
+                let processes = group.processes(Self::info);
+                match name {
+                    Some(name) => processes
+                        .into_iter()
+                        .find(|process| process.name == name)
+                        .map(|process| vec![process])
+                        .ok_or_else(|| anyhow::anyhow!("no process named '{}'", name)),
+                    None => Ok(processes),
+                }
+            }
+            Request::Start { name } => Self::act(group, &name, ProcessScreen::start),
+            Request::Events
+            | Request::Attach { .. }
+            | Request::Restart { .. }
+            | Request::Stop { .. } => Err(anyhow::anyhow!("handled by serve")),
+        };
+        match result {
+            Ok(processes) => Response::ok(processes),
+            Err(e) => Response::error(e.to_string()),
+        }
+    }
+
+    /// Restarts the process called `name`, replying once its new run has started.
+    async fn restart(group: &ProcessGroup, name: &str) -> Response {
+        let result = group
+            .restart(name, StartedBy::Ctl)
+            .await
+            .and_then(|()| Self::act(group, name, |_| Ok(())));
+        match result {
+            Ok(processes) => Response::ok(processes),
+            Err(e) => Response::error(e.to_string()),
+        }
+    }
+
+    /// Stops the process called `name`, replying once it has exited, which can
+    /// take as long as [`ProcessGroup::wait_for_exit`] does.
+    async fn stop(group: &ProcessGroup, name: &str) -> Response {
+        if let Err(e) = group.with_process(name, |process| process.stop()) {
+            return Response::error(e.to_string());
+        }
+        group.wait_for_exit(name).await;
+        let result = Self::act(group, name, |process| {
+            if process.status().is_running() {
+                anyhow::bail!("{} is still running after being killed", name);
+            }
+            Ok(())
+        });
+        match result {
+            Ok(processes) => Response::ok(processes),
+            Err(e) => Response::error(e.to_string()),
+        }
+    }
+
+    /// Runs `action` on the process called `name`, replying with its new status.
+    fn act(
+        group: &ProcessGroup,
+        name: &str,
+        action: impl FnOnce(&mut ProcessScreen) -> anyhow::Result<()>,
+    ) -> anyhow::Result<Vec<ProcessInfo>> {
+        group.with_process(name, |process| {
+            action(process)?;
+            Ok(vec![Self::info(process)])
+        })
+    }
+
+    fn info(process: &ProcessScreen) -> ProcessInfo {
+        let status = process.status();
+        ProcessInfo {
+            name: process.name.clone(),
+            state: status.state().to_owned(),
+            pid: process.pid(),
+            exit_code: status.exit_status().map(|status| status.exit_code()),
+            exit_status: status.exit_status().map(ToString::to_string),
+            started: Some(logs::timestamp(process.started_at())),
+            started_by: Some(process.started_by().to_string()),
+            uptime: process.uptime().map(|uptime| uptime.as_secs()),
+            restarts: Some(process.restarts()),
+            runs: process.runs().iter().map(Self::run_info).collect(),
+        }
+    }
+
+    fn run_info(run: &Run) -> RunInfo {
+        RunInfo {
+            started: logs::timestamp(run.started),
+            started_by: run.started_by.to_string(),
+            duration: run.duration.as_secs(),
+            ended: run.ended.clone(),
+        }
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        self.task.abort();
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use super::*;
+
+    #[test]
+    fn reports_previous_runs_in_the_status() {
+        let run = Run {
+            started: SystemTime::UNIX_EPOCH,
+            started_by: StartedBy::Launch,
+            duration: Duration::from_secs(90),
+            ended: "Exited with code 1".to_owned(),
+        };
+        let info = ProcessInfo {
+            name: "web".to_owned(),
+            state: "running".to_owned(),
+            restarts: Some(1),
+            runs: vec![Server::run_info(&run)],
+            ..ProcessInfo::default()
+        };
+        assert_eq!(
+            concat!(
+                r#"{"name":"web","state":"running","restarts":1,"runs":["#,
+                r#"{"started":"1970-01-01T00:00:00.000Z","started_by":"launch","#,
+                r#""duration":90,"ended":"Exited with code 1"}]}"#
+            ),
+            serde_json::to_string(&info).unwrap()
+        );
+    }
+}
@@ -0,0 +1,114 @@
+use std::{
+    fs::OpenOptions,
+    path::Path,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Context;
+use serde::Serialize;
+use tokio::{io::AsyncWriteExt, sync::broadcast, task::JoinHandle};
+
+/// Events a subscriber can fall behind by before it misses some.
+const BACKLOG: usize = 1024;
+
+/// Something that happened to a process.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+pub(crate) enum Event {
+    Started {
+        process: String,
+        pid: Option<u32>,
+    },
+    Exited {
+        process: String,
+        code: u32,
+        status: String,
+    },
+    Stopped {
+        process: String,
+    },
+    Restarted {
+        process: String,
+    },
+    /// The process printed its `--ready` pattern.
+    Ready {
+        process: String,
+    },
+    /// The process printed its `--unhealthy` pattern, and isn't ready until it
+    /// prints its `--ready` pattern again.
+    Unhealthy {
+        process: String,
+        line: String,
+    },
+    /// The process printed a line matching a `--watch` pattern.
+    OutputMatched {
+        process: String,
+        pattern: String,
+        line: String,
+    },
+}
+
+#[derive(Serialize)]
+struct Record<'a> {
+    /// Milliseconds since the Unix epoch.
+    time: u64,
+    #[serde(flatten)]
+    event: &'a Event,
+}
+
+/// Delivers events to any number of subscribers as lines of JSON.
+#[derive(Debug, Clone)]
+pub(crate) struct EventBus {
+    sender: broadcast::Sender<Arc<str>>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self {
+            sender: broadcast::channel(BACKLOG).0,
+        }
+    }
+}
+
+impl EventBus {
+    pub(crate) fn publish(&self, event: &Event) {
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| {
+                u64::try_from(since.as_millis()).unwrap_or(u64::MAX)
+            });
+        if let Ok(json) = serde_json::to_string(&Record { time, event }) {
+            // Sending fails when nobody's listening, which is fine.
+            let _ = self.sender.send(json.into());
+        }
+    }
+
+    pub(crate) fn subscribe(&self) -> broadcast::Receiver<Arc<str>> {
+        self.sender.subscribe()
+    }
+
+    /// Appends every event to the file at `path` from now on.
+    pub(crate) fn write_to(&self, path: &Path) -> anyhow::Result<JoinHandle<()>> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed opening events file {:?}", path))?;
+        let mut file = tokio::fs::File::from_std(file);
+        let mut events = self.subscribe();
+        Ok(tokio::spawn(async move {
+            loop {
+                let line = match events.recv().await {
+                    Ok(line) => line,
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                let written = file.write_all(format!("{}\n", line).as_bytes()).await;
+                if written.is_err() || file.flush().await.is_err() {
+                    break;
+                }
+            }
+        }))
+    }
+}
@@ -0,0 +1,5 @@
+mod key;
+mod mouse;
+
+pub(crate) use key::encode_key;
+pub(crate) use mouse::encode_mouse;
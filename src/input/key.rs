@@ -0,0 +1,205 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// Encodes a key press as the bytes xterm would send for it, or `None` for keys
+/// that have no encoding (lock keys, media keys, lone modifiers, ...).
+///
+/// `application_cursor` is the child's DECCKM state, which switches the cursor
+/// keys from `CSI` to `SS3` sequences.
+pub(crate) fn encode_key(key: KeyEvent, application_cursor: bool) -> Option<Vec<u8>> {
+    let modifiers = key.modifiers;
+    let alt = modifiers.contains(KeyModifiers::ALT);
+    let ctrl = modifiers.contains(KeyModifiers::CONTROL);
+
+    let bytes = match key.code {
+        KeyCode::Char(c) if ctrl => vec![control_char(c)?],
+        KeyCode::Char(c) => c.to_string().into_bytes(),
+        KeyCode::Enter => vec![b'\r'],
+        KeyCode::Backspace if ctrl => vec![0x08],
+        KeyCode::Backspace => vec![0x7f],
+        KeyCode::Tab => vec![b'\t'],
+        KeyCode::BackTab => b"\x1b[Z".to_vec(),
+        KeyCode::Esc => vec![0x1b],
+        KeyCode::Null => vec![0],
+        KeyCode::Up => cursor_key(b'A', modifiers, application_cursor),
+        KeyCode::Down => cursor_key(b'B', modifiers, application_cursor),
+        KeyCode::Right => cursor_key(b'C', modifiers, application_cursor),
+        KeyCode::Left => cursor_key(b'D', modifiers, application_cursor),
+        KeyCode::Home => cursor_key(b'H', modifiers, application_cursor),
+        KeyCode::End => cursor_key(b'F', modifiers, application_cursor),
+        KeyCode::KeypadBegin => cursor_key(b'E', modifiers, application_cursor),
+        KeyCode::Insert => tilde_key(2, modifiers),
+        KeyCode::Delete => tilde_key(3, modifiers),
+        KeyCode::PageUp => tilde_key(5, modifiers),
+        KeyCode::PageDown => tilde_key(6, modifiers),
+        // F1-F4 are always SS3 sequences, as cursor keys are in application mode.
+        KeyCode::F(n @ 1..=4) => cursor_key(b"PQRS"[usize::from(n - 1)], modifiers, true),
+        KeyCode::F(n @ 5..=12) => tilde_key(
+            [15, 17, 18, 19, 20, 21, 23, 24][usize::from(n - 5)],
+            modifiers,
+        ),
+        KeyCode::F(_)
+        | KeyCode::CapsLock
+        | KeyCode::ScrollLock
+        | KeyCode::NumLock
+        | KeyCode::PrintScreen
+        | KeyCode::Pause
+        | KeyCode::Menu
+        | KeyCode::Media(_)
+        | KeyCode::Modifier(_) => return None,
+    };
+
+    // Alt is sent as an escape prefix on keys that don't carry modifiers themselves.
+    let prefixed = alt
+        && matches!(
+            key.code,
+            KeyCode::Char(_) | KeyCode::Enter | KeyCode::Backspace | KeyCode::Tab | KeyCode::Esc
+        );
+    if prefixed {
+        let mut escaped = vec![0x1b];
+        escaped.extend(bytes);
+        return Some(escaped);
+    }
+    Some(bytes)
+}
+
+/// The byte Ctrl plus `c` produces, following xterm.
+fn control_char(c: char) -> Option<u8> {
+    match c {
+        'a'..='z' => Some(c as u8 - b'a' + 1),
+        'A'..='Z' => Some(c as u8 - b'A' + 1),
+        '@' | ' ' | '2' => Some(0),
+        '[' | '3' => Some(0x1b),
+        '\\' | '4' => Some(0x1c),
+        ']' | '5' => Some(0x1d),
+        '^' | '6' => Some(0x1e),
+        '_' | '/' | '7' => Some(0x1f),
+        '?' | '8' => Some(0x7f),
+        _ => c.is_ascii().then_some(c as u8),
+    }
+}
+
+/// The xterm modifier parameter: 1 plus a bitmask of shift, alt and ctrl.
+fn modifier_param(modifiers: KeyModifiers) -> Option<u8> {
+    let mut param = 0;
+    if modifiers.contains(KeyModifiers::SHIFT) {
+        param |= 1;
+    }
+    if modifiers.contains(KeyModifiers::ALT) {
+        param |= 2;
+    }
+    if modifiers.contains(KeyModifiers::CONTROL) {
+        param |= 4;
+    }
+    (param > 0).then_some(param + 1)
+}
+
+fn cursor_key(final_byte: u8, modifiers: KeyModifiers, application_cursor: bool) -> Vec<u8> {
+    match modifier_param(modifiers) {
+        Some(param) => format!("\x1b[1;{}{}", param, char::from(final_byte)).into_bytes(),
+        None if application_cursor => vec![0x1b, b'O', final_byte],
+        None => vec![0x1b, b'[', final_byte],
+    }
+}
+
+fn tilde_key(number: u8, modifiers: KeyModifiers) -> Vec<u8> {
+    modifier_param(modifiers).map_or_else(
+        || format!("\x1b[{}~", number).into_bytes(),
+        |param| format!("\x1b[{};{}~", number, param).into_bytes(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(code: KeyCode, modifiers: KeyModifiers) -> Option<Vec<u8>> {
+        encode_key(KeyEvent::new(code, modifiers), false)
+    }
+
+    #[test]
+    fn plain_and_control_chars() {
+        assert_eq!(
+            Some(b"q".to_vec()),
+            encode(KeyCode::Char('q'), KeyModifiers::NONE)
+        );
+        assert_eq!(
+            Some("é".as_bytes().to_vec()),
+            encode(KeyCode::Char('é'), KeyModifiers::NONE)
+        );
+        assert_eq!(
+            Some(vec![3]),
+            encode(KeyCode::Char('c'), KeyModifiers::CONTROL)
+        );
+        assert_eq!(
+            Some(vec![0]),
+            encode(KeyCode::Char(' '), KeyModifiers::CONTROL)
+        );
+        assert_eq!(
+            Some(b"\x1bx".to_vec()),
+            encode(KeyCode::Char('x'), KeyModifiers::ALT)
+        );
+        assert_eq!(
+            Some(vec![0x1b, 0x01]),
+            encode(
+                KeyCode::Char('a'),
+                KeyModifiers::CONTROL | KeyModifiers::ALT
+            )
+        );
+    }
+
+    #[test]
+    fn editing_keys() {
+        assert_eq!(
+            Some(b"\t".to_vec()),
+            encode(KeyCode::Tab, KeyModifiers::NONE)
+        );
+        assert_eq!(
+            Some(b"\x1b[Z".to_vec()),
+            encode(KeyCode::BackTab, KeyModifiers::SHIFT)
+        );
+        assert_eq!(
+            Some(vec![0x7f]),
+            encode(KeyCode::Backspace, KeyModifiers::NONE)
+        );
+        assert_eq!(
+            Some(b"\x1b[3~".to_vec()),
+            encode(KeyCode::Delete, KeyModifiers::NONE)
+        );
+        assert_eq!(
+            Some(b"\x1b[5;5~".to_vec()),
+            encode(KeyCode::PageUp, KeyModifiers::CONTROL)
+        );
+    }
+
+    #[test]
+    fn cursor_keys_follow_application_mode() {
+        let up = KeyEvent::new(KeyCode::Up, KeyModifiers::NONE);
+        assert_eq!(Some(b"\x1b[A".to_vec()), encode_key(up, false));
+        assert_eq!(Some(b"\x1bOA".to_vec()), encode_key(up, true));
+
+        let ctrl_left = KeyEvent::new(KeyCode::Left, KeyModifiers::CONTROL);
+        assert_eq!(Some(b"\x1b[1;5D".to_vec()), encode_key(ctrl_left, true));
+    }
+
+    #[test]
+    fn function_keys() {
+        assert_eq!(
+            Some(b"\x1bOP".to_vec()),
+            encode(KeyCode::F(1), KeyModifiers::NONE)
+        );
+        assert_eq!(
+            Some(b"\x1b[1;2S".to_vec()),
+            encode(KeyCode::F(4), KeyModifiers::SHIFT)
+        );
+        assert_eq!(
+            Some(b"\x1b[15~".to_vec()),
+            encode(KeyCode::F(5), KeyModifiers::NONE)
+        );
+        assert_eq!(
+            Some(b"\x1b[24;3~".to_vec()),
+            encode(KeyCode::F(12), KeyModifiers::ALT)
+        );
+        assert_eq!(None, encode(KeyCode::F(13), KeyModifiers::NONE));
+        assert_eq!(None, encode(KeyCode::CapsLock, KeyModifiers::NONE));
+    }
+}
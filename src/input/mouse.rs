@@ -0,0 +1,168 @@
+use crossterm::event::{KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use vt100::{MouseProtocolEncoding, MouseProtocolMode};
+
+/// Encodes a mouse event as the xterm report a child asked for with its mouse
+/// protocol mode, or `None` if the child isn't interested in this event.
+///
+/// The event's `column` and `row` must already be relative to the child's screen.
+pub(crate) fn encode_mouse(
+    event: MouseEvent,
+    mode: MouseProtocolMode,
+    encoding: MouseProtocolEncoding,
+) -> Option<Vec<u8>> {
+    let (button, release) = match event.kind {
+        MouseEventKind::Down(button) => (button_code(button), false),
+        MouseEventKind::Up(button) => match mode {
+            MouseProtocolMode::None | MouseProtocolMode::Press => return None,
+            _ => (button_code(button), true),
+        },
+        MouseEventKind::Drag(button) => match mode {
+            MouseProtocolMode::ButtonMotion | MouseProtocolMode::AnyMotion => {
+                (button_code(button) + 32, false)
+            }
+            _ => return None,
+        },
+        MouseEventKind::Moved => match mode {
+            // Motion with no button held reports as "release" plus the motion flag.
+            MouseProtocolMode::AnyMotion => (3 + 32, false),
+            _ => return None,
+        },
+        MouseEventKind::ScrollUp => (64, false),
+        MouseEventKind::ScrollDown => (65, false),
+        MouseEventKind::ScrollLeft => (66, false),
+        MouseEventKind::ScrollRight => (67, false),
+    };
+    if mode == MouseProtocolMode::None {
+        return None;
+    }
+
+    let button = button | modifier_code(event.modifiers);
+    let (col, row) = (u32::from(event.column) + 1, u32::from(event.row) + 1);
+
+    match encoding {
+        MouseProtocolEncoding::Sgr => {
+            let suffix = if release { 'm' } else { 'M' };
+            Some(format!("\x1b[<{};{};{}{}", button, col, row, suffix).into_bytes())
+        }
+        MouseProtocolEncoding::Default => {
+            // Legacy reports can't address anything past column/row 223.
+            let button = if release { 3 | (button & !3) } else { button };
+            let encode = |value: u32| u8::try_from(value + 32).ok();
+            Some(vec![
+                0x1b,
+                b'[',
+                b'M',
+                encode(button)?,
+                encode(col)?,
+                encode(row)?,
+            ])
+        }
+        MouseProtocolEncoding::Utf8 => {
+            let button = if release { 3 | (button & !3) } else { button };
+            let mut bytes = b"\x1b[M".to_vec();
+            for value in [button, col, row] {
+                let c = char::from_u32(value + 32)?;
+                bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+            }
+            Some(bytes)
+        }
+    }
+}
+
+fn button_code(button: MouseButton) -> u32 {
+    match button {
+        MouseButton::Left => 0,
+        MouseButton::Middle => 1,
+        MouseButton::Right => 2,
+    }
+}
+
+fn modifier_code(modifiers: KeyModifiers) -> u32 {
+    let mut code = 0;
+    if modifiers.contains(KeyModifiers::SHIFT) {
+        code |= 4;
+    }
+    if modifiers.contains(KeyModifiers::ALT) {
+        code |= 8;
+    }
+    if modifiers.contains(KeyModifiers::CONTROL) {
+        code |= 16;
+    }
+    code
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(kind: MouseEventKind, column: u16, row: u16) -> MouseEvent {
+        MouseEvent {
+            kind,
+            column,
+            row,
+            modifiers: KeyModifiers::NONE,
+        }
+    }
+
+    #[test]
+    fn ignored_without_mouse_mode() {
+        let click = event(MouseEventKind::Down(MouseButton::Left), 0, 0);
+        let encoded = encode_mouse(
+            click,
+            MouseProtocolMode::None,
+            MouseProtocolEncoding::Default,
+        );
+
+        assert_eq!(None, encoded);
+    }
+
+    #[test]
+    fn sgr_press_and_release() {
+        let mode = MouseProtocolMode::PressRelease;
+        let press = event(MouseEventKind::Down(MouseButton::Left), 4, 9);
+        let release = event(MouseEventKind::Up(MouseButton::Left), 4, 9);
+
+        assert_eq!(
+            Some(b"\x1b[<0;5;10M".to_vec()),
+            encode_mouse(press, mode, MouseProtocolEncoding::Sgr)
+        );
+        assert_eq!(
+            Some(b"\x1b[<0;5;10m".to_vec()),
+            encode_mouse(release, mode, MouseProtocolEncoding::Sgr)
+        );
+    }
+
+    #[test]
+    fn default_encoding_wheel() {
+        let wheel = event(MouseEventKind::ScrollUp, 0, 1);
+        let encoded = encode_mouse(
+            wheel,
+            MouseProtocolMode::Press,
+            MouseProtocolEncoding::Default,
+        );
+
+        assert_eq!(Some(vec![0x1b, b'[', b'M', 96, 33, 34]), encoded);
+    }
+
+    #[test]
+    fn motion_requires_motion_mode() {
+        let drag = event(MouseEventKind::Drag(MouseButton::Left), 0, 0);
+
+        assert_eq!(
+            None,
+            encode_mouse(
+                drag,
+                MouseProtocolMode::PressRelease,
+                MouseProtocolEncoding::Sgr
+            )
+        );
+        assert_eq!(
+            Some(b"\x1b[<32;1;1M".to_vec()),
+            encode_mouse(
+                drag,
+                MouseProtocolMode::ButtonMotion,
+                MouseProtocolEncoding::Sgr
+            )
+        );
+    }
+}
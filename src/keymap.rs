@@ -0,0 +1,479 @@
+use std::{
+    collections::BTreeMap,
+    fmt,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use anyhow::Context;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+
+/// A key with its modifiers, written like tmux does: `C-a`, `M-x`, `S-Left`, `F5`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct KeyChord {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    pub(crate) const fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        Self { code, modifiers }
+    }
+
+    /// The chord a key press matches. Shift is part of a character itself, so
+    /// `A` matches shift-a.
+    pub(crate) fn from_event(key: KeyEvent) -> Self {
+        let mut modifiers =
+            key.modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT);
+        if matches!(key.code, KeyCode::Char(_) | KeyCode::BackTab) {
+            modifiers.remove(KeyModifiers::SHIFT);
+        }
+        Self::new(key.code, modifiers)
+    }
+
+    pub(crate) fn matches(self, key: KeyEvent) -> bool {
+        self == Self::from_event(key)
+    }
+}
+
+const NAMED_KEYS: &[(&str, KeyCode)] = &[
+    ("Enter", KeyCode::Enter),
+    ("Tab", KeyCode::Tab),
+    ("BTab", KeyCode::BackTab),
+    ("Esc", KeyCode::Esc),
+    ("Escape", KeyCode::Esc),
+    ("Space", KeyCode::Char(' ')),
+    ("BSpace", KeyCode::Backspace),
+    ("Backspace", KeyCode::Backspace),
+    ("Up", KeyCode::Up),
+    ("Down", KeyCode::Down),
+    ("Left", KeyCode::Left),
+    ("Right", KeyCode::Right),
+    ("Home", KeyCode::Home),
+    ("End", KeyCode::End),
+    ("PageUp", KeyCode::PageUp),
+    ("PgUp", KeyCode::PageUp),
+    ("PageDown", KeyCode::PageDown),
+    ("PgDn", KeyCode::PageDown),
+    ("Insert", KeyCode::Insert),
+    ("Delete", KeyCode::Delete),
+];
+
+impl FromStr for KeyChord {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = s;
+        // A trailing `-` is the minus key itself, as in `C--`.
+        while let Some((prefix, key)) = rest.split_once('-').filter(|(_, key)| !key.is_empty()) {
+            modifiers |= match prefix.to_ascii_lowercase().as_str() {
+                "c" | "ctrl" => KeyModifiers::CONTROL,
+                "m" | "alt" | "meta" => KeyModifiers::ALT,
+                "s" | "shift" => KeyModifiers::SHIFT,
+                _ => return Err(format!("unknown modifier '{}' in key '{}'", prefix, s)),
+            };
+            rest = key;
+        }
+
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => parse_named(rest).ok_or_else(|| format!("unknown key '{}'", s))?,
+        };
+        let chord = Self::new(code, modifiers);
+        // Normalise as key presses are, so `S-a` and `A` are the same chord.
+        Ok(match code {
+            KeyCode::Char(c) if modifiers.contains(KeyModifiers::SHIFT) => Self::new(
+                KeyCode::Char(c.to_ascii_uppercase()),
+                modifiers - KeyModifiers::SHIFT,
+            ),
+            _ => chord,
+        })
+    }
+}
+
+fn parse_named(name: &str) -> Option<KeyCode> {
+    if let Some(n) = name
+        .strip_prefix(['F', 'f'])
+        .and_then(|n| n.parse::<u8>().ok())
+    {
+        return (1..=12).contains(&n).then_some(KeyCode::F(n));
+    }
+    NAMED_KEYS
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, code)| *code)
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (modifier, prefix) in [
+            (KeyModifiers::CONTROL, "C-"),
+            (KeyModifiers::ALT, "M-"),
+            (KeyModifiers::SHIFT, "S-"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                f.write_str(prefix)?;
+            }
+        }
+        match self.code {
+            KeyCode::Char(' ') => f.write_str("Space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::F(n) => write!(f, "F{}", n),
+            code => {
+                let name = NAMED_KEYS.iter().find(|(_, named)| *named == code);
+                f.write_str(name.map_or("?", |(name, _)| name))
+            }
+        }
+    }
+}
+
+/// Something hawk itself does, rather than the focused process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Action {
+    Quit,
+    FocusNext,
+    FocusPrev,
+    Restart,
+    Zoom,
+    ScrollUp,
+    ScrollDown,
+    Search,
+    Copy,
+    Broadcast,
+    Mark,
+    Logs,
+    Filter,
+    Sidebar,
+    Layout,
+    Hide,
+    Details,
+}
+
+/// Action names as written in the keymap file, with their footer labels.
+const ACTIONS: &[(&str, Action, &str)] = &[
+    ("quit", Action::Quit, "quit"),
+    ("focus-next", Action::FocusNext, "next"),
+    ("focus-prev", Action::FocusPrev, "prev"),
+    ("restart", Action::Restart, "restart"),
+    ("zoom", Action::Zoom, "zoom"),
+    ("scroll-up", Action::ScrollUp, "scroll"),
+    ("scroll-down", Action::ScrollDown, "scroll down"),
+    ("search", Action::Search, "search"),
+    ("copy", Action::Copy, "copy"),
+    ("broadcast", Action::Broadcast, "broadcast"),
+    ("mark", Action::Mark, "mark"),
+    ("logs", Action::Logs, "all logs"),
+    ("filter", Action::Filter, "filter"),
+    ("sidebar", Action::Sidebar, "sidebar"),
+    ("layout", Action::Layout, "tabs/tiles"),
+    ("hide", Action::Hide, "hide/show"),
+    ("details", Action::Details, "details"),
+];
+
+/// The actions the footer hint names, which fit on an 80 column terminal.
+const HINTED: &[Action] = &[
+    Action::Quit,
+    Action::FocusNext,
+    Action::Restart,
+    Action::Zoom,
+    Action::ScrollUp,
+    Action::Search,
+    Action::Logs,
+];
+
+/// Unbinds a key in the keymap file.
+const UNBOUND: &str = "none";
+
+impl FromStr for Action {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ACTIONS
+            .iter()
+            .find(|(name, _, _)| *name == s)
+            .map(|(_, action, _)| *action)
+            .ok_or_else(|| {
+                let names: Vec<_> = ACTIONS.iter().map(|(name, _, _)| *name).collect();
+                format!(
+                    "unknown action '{}', expected one of: {}, {}",
+                    s,
+                    names.join(", "),
+                    UNBOUND
+                )
+            })
+    }
+}
+
+/// The keymap file, e.g. `~/.config/hawk/keys.toml`:
+///
+/// ```toml
+/// prefix = "C-b"
+///
+/// # Keys read after the prefix.
+/// [keys]
+/// x = "restart"
+/// r = "none"
+///
+/// # Keys that work without the prefix, and so never reach the process.
+/// [root]
+/// "M-Right" = "focus-next"
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct KeymapFile {
+    prefix: Option<String>,
+    #[serde(default)]
+    keys: BTreeMap<String, String>,
+    #[serde(default)]
+    root: BTreeMap<String, String>,
+}
+
+/// Which keys reach hawk. Everything else goes to the focused process, and hawk's
+/// own commands are only read after the prefix key, as in tmux, unless they're
+/// bound in the root table.
+#[derive(Debug, Clone)]
+pub(crate) struct Keymap {
+    pub(crate) prefix: KeyChord,
+    commands: Vec<(KeyChord, Action)>,
+    root: Vec<(KeyChord, Action)>,
+}
+
+impl Keymap {
+    pub(crate) fn new(prefix: KeyChord) -> Self {
+        let key = |code| KeyChord::new(code, KeyModifiers::NONE);
+        let commands = vec![
+            (key(KeyCode::Char('q')), Action::Quit),
+            (key(KeyCode::Char('n')), Action::FocusNext),
+            (key(KeyCode::Right), Action::FocusNext),
+            (key(KeyCode::Char('p')), Action::FocusPrev),
+            (key(KeyCode::Left), Action::FocusPrev),
+            (key(KeyCode::Char('r')), Action::Restart),
+            (key(KeyCode::Char('z')), Action::Zoom),
+            (key(KeyCode::PageUp), Action::ScrollUp),
+            (key(KeyCode::PageDown), Action::ScrollDown),
+            (key(KeyCode::Char('/')), Action::Search),
+            (key(KeyCode::Char('[')), Action::Copy),
+            (key(KeyCode::Char('b')), Action::Broadcast),
+            (key(KeyCode::Char('m')), Action::Mark),
+            (key(KeyCode::Char('l')), Action::Logs),
+            (key(KeyCode::Char('f')), Action::Filter),
+            (key(KeyCode::Char('s')), Action::Sidebar),
+            (key(KeyCode::Char('t')), Action::Layout),
+            (key(KeyCode::Char('h')), Action::Hide),
+            (key(KeyCode::Char('i')), Action::Details),
+        ];
+        Self {
+            prefix,
+            commands,
+            root: Vec::new(),
+        }
+    }
+
+    /// Loads the keymap from `path`, or from `~/.config/hawk/keys.toml` if it
+    /// exists, on top of the defaults. `prefix` overrides the file's prefix.
+    pub(crate) fn load(path: Option<&Path>, prefix: Option<KeyChord>) -> anyhow::Result<Self> {
+        // Only the default location is optional; a path that was asked for must exist.
+        let path = path
+            .map(Path::to_path_buf)
+            .or_else(|| default_path().filter(|path| path.exists()));
+        let file = match path {
+            Some(path) => {
+                let content = std::fs::read_to_string(&path)
+                    .with_context(|| format!("Failed reading keymap {:?}", path))?;
+                Self::parse(&content, prefix)
+                    .with_context(|| format!("Invalid keymap {:?}", path))?
+            }
+            _ => Self::new(prefix.unwrap_or(DEFAULT_PREFIX)),
+        };
+        Ok(file)
+    }
+
+    fn parse(content: &str, prefix: Option<KeyChord>) -> anyhow::Result<Self> {
+        let file: KeymapFile = toml::from_str(content)?;
+        let prefix = match (prefix, &file.prefix) {
+            (Some(prefix), _) => prefix,
+            (None, Some(prefix)) => prefix
+                .parse()
+                .map_err(|e| anyhow::anyhow!("prefix: {}", e))?,
+            (None, None) => DEFAULT_PREFIX,
+        };
+
+        let mut keymap = Self::new(prefix);
+        for (table, bindings) in [("keys", &file.keys), ("root", &file.root)] {
+            for (key, action) in bindings {
+                let chord: KeyChord = key
+                    .parse()
+                    .map_err(|e| anyhow::anyhow!("[{}] {}", table, e))?;
+                if chord == prefix {
+                    anyhow::bail!(
+                        "[{}] '{}' is the prefix key, which can't be rebound",
+                        table,
+                        key
+                    );
+                }
+                let action = match action.as_str() {
+                    UNBOUND => None,
+                    action => Some(
+                        action
+                            .parse()
+                            .map_err(|e| anyhow::anyhow!("[{}] '{}': {}", table, key, e))?,
+                    ),
+                };
+                let bindings = if table == "root" {
+                    &mut keymap.root
+                } else {
+                    &mut keymap.commands
+                };
+                bindings.retain(|(bound, _)| *bound != chord);
+                bindings.extend(action.map(|action| (chord, action)));
+            }
+        }
+        Ok(keymap)
+    }
+
+    /// The command bound to `key` when it follows the prefix.
+    pub(crate) fn command(&self, key: KeyEvent) -> Option<Action> {
+        Self::lookup(&self.commands, key)
+    }
+
+    /// The command bound to `key` without the prefix.
+    pub(crate) fn root(&self, key: KeyEvent) -> Option<Action> {
+        Self::lookup(&self.root, key)
+    }
+
+    fn lookup(bindings: &[(KeyChord, Action)], key: KeyEvent) -> Option<Action> {
+        bindings
+            .iter()
+            .find(|(chord, _)| chord.matches(key))
+            .map(|(_, action)| *action)
+    }
+
+    /// A summary of the main commands for the footer, such as "C-a then: q quit, n next".
+    pub(crate) fn hint(&self) -> String {
+        let commands: Vec<_> = ACTIONS
+            .iter()
+            .filter(|(_, action, _)| HINTED.contains(action))
+            .filter_map(|(_, action, label)| {
+                let (chord, _) = self.commands.iter().find(|(_, bound)| bound == action)?;
+                Some(format!("{} {}", chord, label))
+            })
+            .collect();
+        format!("{} then: {}", self.prefix, commands.join(", "))
+    }
+
+    /// The keys that perform `action`, such as "C-a b", if it's bound at all.
+    pub(crate) fn describe(&self, action: Action) -> Option<String> {
+        if let Some((chord, _)) = self.root.iter().find(|(_, bound)| *bound == action) {
+            return Some(chord.to_string());
+        }
+        let (chord, _) = self.commands.iter().find(|(_, bound)| *bound == action)?;
+        Some(format!("{} {}", self.prefix, chord))
+    }
+}
+
+const DEFAULT_PREFIX: KeyChord = KeyChord::new(KeyCode::Char('a'), KeyModifiers::CONTROL);
+
+/// `$XDG_CONFIG_HOME/hawk/keys.toml`, or `~/.config/hawk/keys.toml`.
+fn default_path() -> Option<PathBuf> {
+    let config = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config.join("hawk").join("keys.toml"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_chords() {
+        let ctrl_a = KeyChord::new(KeyCode::Char('a'), KeyModifiers::CONTROL);
+        assert_eq!(Ok(ctrl_a), "C-a".parse());
+        assert_eq!(Ok(ctrl_a), "Ctrl-a".parse());
+        assert_eq!(
+            Ok(KeyChord::new(KeyCode::Char('-'), KeyModifiers::ALT)),
+            "M--".parse()
+        );
+        assert_eq!(
+            Ok(KeyChord::new(KeyCode::PageUp, KeyModifiers::SHIFT)),
+            "S-pageup".parse()
+        );
+        assert_eq!(
+            Ok(KeyChord::new(KeyCode::Char('A'), KeyModifiers::NONE)),
+            "S-a".parse()
+        );
+        assert!("C-".parse::<KeyChord>().is_err());
+        assert!("X-a".parse::<KeyChord>().is_err());
+        assert!("F13".parse::<KeyChord>().is_err());
+        assert_eq!("C-a", ctrl_a.to_string());
+    }
+
+    #[test]
+    fn keymap_file_overrides_defaults() {
+        let keymap = Keymap::parse(
+            "prefix = \"C-b\"\n[keys]\nx = \"restart\"\nr = \"none\"\n[root]\nM-Right = \"focus-next\"\n",
+            None,
+        )
+        .unwrap();
+        let key = KeyEvent::new;
+
+        assert_eq!("C-b", keymap.prefix.to_string());
+        assert_eq!(
+            Some(Action::Restart),
+            keymap.command(key(KeyCode::Char('x'), KeyModifiers::NONE))
+        );
+        assert_eq!(
+            None,
+            keymap.command(key(KeyCode::Char('r'), KeyModifiers::NONE))
+        );
+        assert_eq!(
+            Some(Action::Quit),
+            keymap.command(key(KeyCode::Char('q'), KeyModifiers::NONE))
+        );
+        assert_eq!(
+            Some(Action::FocusNext),
+            keymap.root(key(KeyCode::Right, KeyModifiers::ALT))
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_keymaps() {
+        let error = |content| Keymap::parse(content, None).unwrap_err().to_string();
+
+        assert!(error("[keys]\nq = \"exit\"").contains("expected one of: quit"));
+        assert!(error("[keys]\n\"X-q\" = \"quit\"").contains("unknown modifier 'X'"));
+        assert!(error("[root]\n\"C-a\" = \"quit\"").contains("prefix key"));
+        assert!(error("prefx = \"C-b\"").contains("unknown field"));
+    }
+
+    #[test]
+    fn requires_a_given_keymap_file() {
+        let missing = Path::new("/nonexistent/keys.toml");
+        let error = Keymap::load(Some(missing), None).unwrap_err();
+        assert!(error.to_string().contains("Failed reading keymap"));
+    }
+
+    #[test]
+    fn hints_the_main_commands() {
+        let hint = Keymap::new(DEFAULT_PREFIX).hint();
+        assert_eq!(
+            "C-a then: q quit, n next, r restart, z zoom, PageUp scroll, / search, l all logs",
+            hint
+        );
+        assert!(hint.len() <= 80);
+
+        let keymap = Keymap::parse("[keys]\nq = \"none\"\nx = \"quit\"\n", None).unwrap();
+        assert!(keymap.hint().starts_with("C-a then: x quit, n next"));
+    }
+
+    #[test]
+    fn matches_shifted_characters() {
+        let chord: KeyChord = "N".parse().unwrap();
+        assert!(chord.matches(KeyEvent::new(KeyCode::Char('N'), KeyModifiers::SHIFT)));
+        assert!(!chord.matches(KeyEvent::new(KeyCode::Char('n'), KeyModifiers::NONE)));
+    }
+}
@@ -50,6 +50,16 @@ where
         }
     }
 
+    pub(crate) fn focus(&mut self, index: usize) {
+        if index < self.items.len() {
+            self.index = index;
+        }
+    }
+
+    pub(crate) fn focused_index(&self) -> usize {
+        self.index
+    }
+
     pub(crate) fn focused(&self) -> T {
         self.items.get(self.index).unwrap().clone()
     }
@@ -0,0 +1,312 @@
+use std::{
+    collections::VecDeque,
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Context;
+use parking_lot::Mutex;
+use regex::Regex;
+
+use crate::ansi;
+
+/// Longest line kept for `all.log`; anything further along is dropped.
+const MAX_LINE: usize = 64 * 1024;
+
+/// How `--log-dir` writes process output.
+#[derive(Debug, Clone)]
+pub(crate) struct LogOptions {
+    pub(crate) dir: PathBuf,
+    /// Write plain text rather than the raw terminal output.
+    pub(crate) strip_ansi: bool,
+    /// Size a log can grow to before it's rotated.
+    pub(crate) max_size: Option<u64>,
+    /// Rotated logs kept alongside the current one.
+    pub(crate) keep: usize,
+}
+
+/// A directory of per-process logs, plus `all.log` with every process's lines.
+#[derive(Debug)]
+pub(crate) struct LogDir {
+    options: LogOptions,
+    all: Mutex<LogFile>,
+}
+
+impl LogDir {
+    pub(crate) fn open(options: LogOptions) -> anyhow::Result<Arc<Self>> {
+        fs::create_dir_all(&options.dir)
+            .with_context(|| format!("Couldn't create log directory {:?}", options.dir))?;
+        let all = LogFile::open(options.dir.join("all.log"), &options)?;
+        Ok(Arc::new(Self {
+            options,
+            all: Mutex::new(all),
+        }))
+    }
+
+    /// Opens `<name>.log` for the process called `name`, which mustn't be
+    /// `all`, whose log would be `all.log`.
+    pub(crate) fn process(self: &Arc<Self>, name: &str) -> anyhow::Result<ProcessLog> {
+        if name == "all" {
+            anyhow::bail!(
+                "--log-dir: a process named 'all' would share all.log with every other process"
+            );
+        }
+        let path = self.options.dir.join(format!("{}.log", name));
+        Ok(ProcessLog {
+            name: name.to_owned(),
+            file: LogFile::open(path, &self.options)?,
+            dir: self.clone(),
+            line: Vec::new(),
+        })
+    }
+}
+
+/// One process's log, fed its output as it arrives.
+#[derive(Debug)]
+pub(crate) struct ProcessLog {
+    name: String,
+    file: LogFile,
+    dir: Arc<LogDir>,
+    /// The line being built up for `all.log` (and for the process's own log
+    /// when stripping escapes, which can be split across reads).
+    line: Vec<u8>,
+}
+
+impl ProcessLog {
+    /// Logs a chunk of output. Failing to write a log shouldn't take the
+    /// process down with it, so errors are dropped.
+    pub(crate) fn write(&mut self, output: &[u8]) {
+        if !self.dir.options.strip_ansi {
+            let _ = self.file.write(output);
+        }
+        for chunk in output.split_inclusive(|byte| *byte == b'\n') {
+            let room = MAX_LINE.saturating_sub(self.line.len());
+            self.line.extend(chunk.iter().take(room));
+            if chunk.ends_with(b"\n") {
+                self.finish_line();
+                self.line.clear();
+            }
+        }
+    }
+
+    /// The log's files, oldest first: the rotated ones, then the current one.
+    pub(crate) fn paths(&self) -> Vec<PathBuf> {
+        self.file.paths()
+    }
+
+    fn finish_line(&mut self) {
+        let line = if self.dir.options.strip_ansi {
+            let line = ansi::strip(&self.line);
+            let _ = self.file.write(line.as_bytes());
+            line
+        } else {
+            String::from_utf8_lossy(&self.line).replace('\r', "")
+        };
+        let line = format!(
+            "{} {} | {}\n",
+            timestamp(SystemTime::now()),
+            self.name,
+            line.trim_end_matches('\n')
+        );
+        let _ = self.dir.all.lock().write(line.as_bytes());
+    }
+}
+
+/// An append-only file that's rotated to `<path>.1`, `<path>.2`, … once it
+/// reaches its maximum size.
+#[derive(Debug)]
+struct LogFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    max_size: Option<u64>,
+    keep: usize,
+}
+
+impl LogFile {
+    fn open(path: PathBuf, options: &LogOptions) -> anyhow::Result<Self> {
+        let file = Self::append(&path).with_context(|| format!("Couldn't open log {:?}", path))?;
+        Ok(Self {
+            size: file.metadata()?.len(),
+            path,
+            file,
+            max_size: options.max_size,
+            keep: options.keep,
+        })
+    }
+
+    fn append(path: &Path) -> io::Result<File> {
+        OpenOptions::new().create(true).append(true).open(path)
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> io::Result<()> {
+        let len = bytes.len() as u64;
+        if self
+            .max_size
+            .is_some_and(|max| self.size > 0 && self.size + len > max)
+        {
+            self.rotate()?;
+        }
+        self.file.write_all(bytes)?;
+        self.size += len;
+        Ok(())
+    }
+
+    fn rotated(&self, n: usize) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{}", n));
+        PathBuf::from(path)
+    }
+
+    fn paths(&self) -> Vec<PathBuf> {
+        let mut paths: Vec<_> = (1..=self.keep).rev().map(|n| self.rotated(n)).collect();
+        paths.push(self.path.clone());
+        paths
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        if self.keep == 0 {
+            self.file.set_len(0)?;
+        } else {
+            for n in (1..self.keep).rev() {
+                let from = self.rotated(n);
+                if from.exists() {
+                    fs::rename(from, self.rotated(n + 1))?;
+                }
+            }
+            fs::rename(&self.path, self.rotated(1))?;
+            self.file = Self::append(&self.path)?;
+        }
+        self.size = 0;
+        Ok(())
+    }
+}
+
+/// The lines of a log that a search found.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct LogMatches {
+    /// The newest matching lines, oldest first, without escape sequences.
+    pub(crate) lines: Vec<String>,
+    /// How many lines matched in all.
+    pub(crate) total: usize,
+}
+
+/// Searches the log files at `paths`, oldest first, for lines matching
+/// `regex`, keeping the last `limit` of them. Missing files are skipped, since
+/// a log may not have been rotated yet.
+pub(crate) fn search(paths: &[PathBuf], regex: &Regex, limit: usize) -> io::Result<LogMatches> {
+    let mut lines = VecDeque::new();
+    let mut total = 0;
+    let mut line = Vec::new();
+    for path in paths {
+        let mut reader = match File::open(path) {
+            Ok(file) => BufReader::new(file),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        };
+        line.clear();
+        while reader.read_until(b'\n', &mut line)? > 0 {
+            let text = ansi::strip(&line);
+            let text = text.trim_end_matches('\n');
+            if !text.is_empty() && regex.is_match(text) {
+                total += 1;
+                lines.push_back(text.to_owned());
+                if lines.len() > limit {
+                    lines.pop_front();
+                }
+            }
+            line.clear();
+        }
+    }
+    Ok(LogMatches {
+        lines: lines.into(),
+        total,
+    })
+}
+
+/// `time` as a UTC timestamp like `2024-05-01T12:34:56.789Z`.
+pub(crate) fn timestamp(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let (year, month, day) = civil_date(since_epoch.as_secs() / 86_400);
+    format!(
+        "{:04}-{:02}-{:02}T{}.{:03}Z",
+        year,
+        month,
+        day,
+        clock(time),
+        since_epoch.subsec_millis()
+    )
+}
+
+/// The UTC time of day of `time`, like `12:34:56`.
+pub(crate) fn clock(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+        % 86_400;
+    format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+/// The year, month and day `days` after 1970-01-01, from Howard Hinnant's
+/// `civil_from_days`.
+fn civil_date(days: u64) -> (u64, u64, u64) {
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn formats_timestamps() {
+        assert_eq!(timestamp(UNIX_EPOCH), "1970-01-01T00:00:00.000Z");
+        let time = UNIX_EPOCH + Duration::from_millis(1_709_210_096_789);
+        assert_eq!(timestamp(time), "2024-02-29T12:34:56.789Z");
+    }
+
+    #[test]
+    fn rotates_logs() {
+        let dir = std::env::temp_dir().join(format!("hawk-logs-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let logs = LogDir::open(LogOptions {
+            dir: dir.clone(),
+            strip_ansi: true,
+            max_size: Some(16),
+            keep: 2,
+        })
+        .unwrap();
+        assert!(logs.process("all").is_err());
+        let mut log = logs.process("web").unwrap();
+        log.write(b"\x1b[1mfirst\x1b[0m line\r\n");
+        log.write(b"sec");
+        log.write(b"ond line\nthird line\nfourth line\n");
+
+        let read = |name: &str| fs::read_to_string(dir.join(name)).unwrap_or_default();
+        assert_eq!(read("web.log"), "fourth line\n");
+        assert_eq!(read("web.log.1"), "third line\n");
+        assert_eq!(read("web.log.2"), "second line\n");
+        assert!(!dir.join("web.log.3").exists());
+        assert!(read("all.log").ends_with(" web | fourth line\n"));
+
+        let found = search(&log.paths(), &Regex::new("^(first|second|f)").unwrap(), 1).unwrap();
+        assert_eq!(found.lines, ["fourth line"]);
+        assert_eq!(found.total, 2);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
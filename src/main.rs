@@ -82,24 +82,45 @@
     missing_docs
 )]
 
+mod ansi;
+mod cast;
+mod clipboard;
+mod config;
+mod control;
+mod events;
+mod input;
+mod keymap;
 mod list;
+mod logs;
 mod process;
 mod procfile;
 mod terminal;
 
 use std::{
     path::{Path, PathBuf},
+    process::ExitCode,
+    sync::Arc,
     time::Duration,
 };
 
-use bytes::Bytes;
-use clap::Parser;
-use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use clap::{Parser, Subcommand};
+use crossterm::event::{self, Event, KeyEventKind};
+use parking_lot::Mutex;
 
 use portable_pty::CommandBuilder;
 use ratatui::{backend::Backend, widgets::Widget, Terminal};
 
-use self::process::ProcessGroup;
+use regex::Regex;
+
+use self::{
+    cast::Recorder,
+    config::{Config, Limits},
+    control::Server,
+    events::EventBus,
+    keymap::{KeyChord, Keymap},
+    logs::{LogDir, LogOptions},
+    process::{ExitPolicy, LayoutMode, Outputs, Patterns, ProcessGroup},
+};
 
 fn shell_cmd<S: AsRef<str>>(cmd: S, options: &[&str], cwd: &Path) -> CommandBuilder {
     let mut cmd = CommandBuilder::new(cmd.as_ref());
@@ -117,58 +138,356 @@ fn shell_cmd<S: AsRef<str>>(cmd: S, options: &[&str], cwd: &Path) -> CommandBuil
 #[command(version, about, long_about = None)]
 struct Args {
     /// Configuration File
-    #[arg(short, long, default_value = "Procfile")]
+    #[arg(short, long, default_value = "Procfile", global = true)]
     config: PathBuf,
+
+    /// Lines of history kept per process, or "unlimited"
+    #[arg(long, value_name = "LINES", default_value = "10000", value_parser = parse_scrollback)]
+    scrollback: usize,
+
+    /// Exit as soon as any process exits
+    #[arg(long)]
+    abort_on_exit: bool,
+
+    /// Exit when the named process exits, propagating its exit code
+    #[arg(long, value_name = "NAME")]
+    exit_with: Option<String>,
+
+    /// Tile every process, or show one at a time in tabs
+    #[arg(long, value_enum, default_value_t)]
+    layout: LayoutMode,
+
+    /// Key that must come before hawk's own commands, e.g. C-b [default: C-a]
+    #[arg(long, value_name = "KEY")]
+    prefix: Option<KeyChord>,
+
+    /// Settings file [default: hawk.toml beside the Procfile]
+    #[arg(long, value_name = "FILE")]
+    settings: Option<PathBuf>,
+
+    /// Keybindings file [default: ~/.config/hawk/keys.toml]
+    #[arg(long, value_name = "FILE")]
+    keys: Option<PathBuf>,
+
+    /// Append process lifecycle events to this file as lines of JSON
+    #[arg(long, value_name = "FILE")]
+    events_file: Option<PathBuf>,
+
+    /// Mark a process ready when its output matches a regex, e.g. web='Listening on'
+    #[arg(long, value_name = "NAME=REGEX", value_parser = parse_pattern)]
+    ready: Vec<(String, Regex)>,
+
+    /// Mark a process unhealthy when its output matches a regex, until it's ready again
+    #[arg(long, value_name = "NAME=REGEX", value_parser = parse_pattern)]
+    unhealthy: Vec<(String, Regex)>,
+
+    /// Publish an event whenever a line of output matches this regex
+    #[arg(long, value_name = "REGEX")]
+    watch: Vec<Regex>,
+
+    /// Write each process's output to <DIR>/<name>.log, and all of it to <DIR>/all.log
+    #[arg(long, value_name = "DIR")]
+    log_dir: Option<PathBuf>,
+
+    /// Strip escape sequences from logs, leaving plain text
+    #[arg(long, requires = "log_dir")]
+    log_strip_ansi: bool,
+
+    /// Rotate logs once they reach this size, e.g. 10M
+    #[arg(long, value_name = "SIZE", requires = "log_dir", value_parser = parse_size)]
+    log_max_size: Option<u64>,
+
+    /// Rotated logs to keep for each process
+    #[arg(
+        long,
+        value_name = "COUNT",
+        default_value = "5",
+        requires = "log_max_size"
+    )]
+    log_keep: usize,
+
+    /// Seconds between samples of each process's CPU and memory use, or 0 not to
+    #[arg(long, value_name = "SECONDS", default_value = "1", value_parser = parse_seconds)]
+    usage_interval: Duration,
+
+    /// Show recent CPU use as a sparkline beside the current use
+    #[arg(long)]
+    sparklines: bool,
+
+    /// Record each process's output to <DIR>/<name>.cast, for `hawk replay`
+    #[arg(long, value_name = "DIR")]
+    record: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Control the hawk running for the Procfile from another terminal
+    Ctl {
+        /// Print hawk's reply as JSON
+        #[arg(long)]
+        json: bool,
+
+        #[command(subcommand)]
+        request: control::Request,
+    },
+    /// Show one process's output from another terminal, detaching with Ctrl-]
+    Attach {
+        /// The process to attach to
+        name: String,
+
+        /// Send keystrokes to the process as well
+        #[arg(short, long)]
+        input: bool,
+    },
+    /// Play back a recording made with --record
+    Replay {
+        /// The .cast file to play
+        file: PathBuf,
+
+        /// How many times faster than recorded to play it
+        #[arg(long, default_value = "1", value_parser = parse_speed)]
+        speed: f64,
+    },
+}
+
+impl Args {
+    fn exit_policy(&self) -> ExitPolicy {
+        ExitPolicy {
+            abort_on_exit: self.abort_on_exit,
+            exit_with: self.exit_with.clone(),
+        }
+    }
+
+    /// The output patterns for the process called `name`.
+    fn patterns(&self, name: &str) -> Patterns {
+        let find = |patterns: &[(String, Regex)]| {
+            patterns
+                .iter()
+                .find(|(process, _)| process == name)
+                .map(|(_, regex)| regex.clone())
+        };
+        Patterns {
+            ready: find(&self.ready),
+            unhealthy: find(&self.unhealthy),
+            watch: self.watch.clone(),
+        }
+    }
+
+    fn log_options(&self) -> Option<LogOptions> {
+        self.log_dir.as_ref().map(|dir| LogOptions {
+            dir: dir.clone(),
+            strip_ansi: self.log_strip_ansi,
+            max_size: self.log_max_size,
+            keep: self.log_keep,
+        })
+    }
 }
 
-fn main() -> anyhow::Result<()> {
+fn parse_pattern(value: &str) -> Result<(String, Regex), String> {
+    let (name, regex) = value
+        .split_once('=')
+        .ok_or_else(|| format!("expected NAME=REGEX, got '{}'", value))?;
+    let regex = Regex::new(regex).map_err(|e| e.to_string())?;
+    Ok((name.to_owned(), regex))
+}
+
+fn parse_size(value: &str) -> Result<u64, String> {
+    let digits = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(digits);
+    let unit = match unit.to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" => 1 << 10,
+        "M" | "MB" => 1 << 20,
+        "G" | "GB" => 1 << 30,
+        _ => return Err(format!("expected a size like 512K or 10M, got '{}'", value)),
+    };
+    number
+        .parse::<u64>()
+        .map(|number| number * unit)
+        .map_err(|_| format!("expected a size like 512K or 10M, got '{}'", value))
+}
+
+fn parse_seconds(value: &str) -> Result<Duration, String> {
+    value
+        .parse()
+        .ok()
+        .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+        .ok_or_else(|| {
+            format!(
+                "expected a number of seconds, like 1 or 0.5, not '{}'",
+                value
+            )
+        })
+}
+
+fn parse_scrollback(value: &str) -> Result<usize, String> {
+    if value == "unlimited" {
+        return Ok(usize::MAX);
+    }
+    value.parse().map_err(|_| {
+        format!(
+            "expected a number of lines or \"unlimited\", got '{}'",
+            value
+        )
+    })
+}
+
+fn parse_speed(value: &str) -> Result<f64, String> {
+    value
+        .parse()
+        .ok()
+        .filter(|speed: &f64| speed.is_finite() && *speed > 0.0)
+        .ok_or_else(|| format!("expected a speed above 0, like 2 or 0.5, not '{}'", value))
+}
+
+fn main() -> anyhow::Result<ExitCode> {
     // Build a tokio runtime
     let rt = tokio::runtime::Builder::new_multi_thread()
         .enable_all()
-        .build()
-        .expect("Failed building the Runtime");
+        .build()?;
 
     // Parse the command line arguments
     let args = Args::parse();
 
     // Run our main function
-    rt.block_on(async_main(args))?;
+    let code = match &args.command {
+        Some(Command::Ctl { json, request }) => {
+            let socket = control::socket_path(&args.config)?;
+            rt.block_on(control::send(&socket, request, *json))?
+        }
+        Some(Command::Attach { name, input }) => {
+            let socket = control::socket_path(&args.config)?;
+            rt.block_on(control::attach(&socket, name, *input))?
+        }
+        Some(Command::Replay { file, speed }) => cast::replay(file, *speed)?,
+        None => rt.block_on(async_main(args))?,
+    };
 
     // Shutdown any lingering processes;
     rt.shutdown_background();
-    Ok(())
+    Ok(ExitCode::from(code))
 }
 
-async fn async_main(args: Args) -> anyhow::Result<()> {
+async fn async_main(args: Args) -> anyhow::Result<u8> {
     let config = tokio::fs::read_to_string(&args.config).await?;
 
-    let procfile = procfile::parse(&config).expect("Failed parsing procfile");
+    let procfile =
+        procfile::parse(&config).map_err(|e| anyhow::anyhow!("Failed parsing procfile: {}", e))?;
 
-    let mut terminal = terminal::setup_terminal()?;
-    let size = terminal.size()?;
-    let mut group = ProcessGroup::new(size.height, size.width);
+    let names = args.exit_with.iter().map(|name| ("--exit-with", name));
+    let names = names.chain(args.ready.iter().map(|(name, _)| ("--ready", name)));
+    let names = names.chain(args.unhealthy.iter().map(|(name, _)| ("--unhealthy", name)));
+    for (flag, name) in names {
+        if !procfile.iter().any(|proc| proc.name == name) {
+            anyhow::bail!("{}: no process named '{}' in {:?}", flag, name, args.config);
+        }
+    }
+
+    let events = EventBus::default();
+    let _events_file = match &args.events_file {
+        Some(path) => Some(events.write_to(path)?),
+        None => None,
+    };
+
+    let keymap = Keymap::load(args.keys.as_deref(), args.prefix)?;
+    let config = Config::load(args.settings.as_deref(), &args.config)?;
+    for name in config.limits.keys() {
+        if !procfile.iter().any(|proc| proc.name == name) {
+            anyhow::bail!(
+                "[limits.{}]: no process named '{}' in {:?}",
+                name,
+                name,
+                args.config
+            );
+        }
+    }
+    let names = config.layout.hidden.iter().map(String::as_str);
+    for name in names.chain(config.layout.placed()) {
+        if !procfile.iter().any(|proc| proc.name == name) {
+            anyhow::bail!("[layout]: no process named '{}' in {:?}", name, args.config);
+        }
+    }
+    let memory_limited = config.limits.values().any(|limits| limits.memory.is_some());
+    if memory_limited && args.usage_interval.is_zero() {
+        anyhow::bail!(
+            "memory limits are checked when usage is sampled, so need a --usage-interval"
+        );
+    }
+    let logs = args.log_options().map(LogDir::open).transpose()?;
+    let socket = control::socket_path(&args.config)?;
+
+    // Everything that can fail happens before the terminal is taken over, so
+    // an error never leaves it in raw mode on the alternate screen.
+    let (cols, rows) = crossterm::terminal::size()?;
+    let mut group = ProcessGroup::new(
+        rows,
+        cols,
+        args.scrollback,
+        keymap,
+        events.clone(),
+        &config,
+        args.layout,
+    );
+    group.show_sparklines(args.sparklines);
+    // Claiming the socket first means a second hawk here stops before it
+    // starts a copy of every process.
+    let _server = Server::start(&socket, group.clone(), events)?;
     let cwd = std::env::current_dir().unwrap();
 
     for proc in procfile {
-        group.add(proc.name, shell_cmd(proc.command, &proc.options, &cwd))?;
+        let outputs = Outputs {
+            patterns: args.patterns(proc.name),
+            log: match &logs {
+                Some(logs) => Some(Arc::new(Mutex::new(logs.process(proc.name)?))),
+                None => None,
+            },
+            record: match &args.record {
+                Some(dir) => Some(Arc::new(Mutex::new(Recorder::create(dir, proc.name)?))),
+                None => None,
+            },
+            ..Outputs::default()
+        };
+        let script = config.limits.get(proc.name).and_then(Limits::ulimit_script);
+        let cmd = match &script {
+            Some(script) => {
+                let mut options = vec!["-c", script, "sh", proc.command];
+                options.extend(&proc.options);
+                shell_cmd("sh", &options, &cwd)
+            }
+            None => shell_cmd(proc.command, &proc.options, &cwd),
+        };
+        group.add(proc.name, cmd, outputs)?;
     }
 
-    run(&mut terminal, group).await?;
+    let _usage = (!args.usage_interval.is_zero()).then(|| group.sample_usage(args.usage_interval));
+
+    let mut terminal = terminal::setup_terminal()?;
+    let result = run(&mut terminal, group, &args.exit_policy());
     terminal::cleanup_terminal(terminal)?;
 
-    Ok(())
+    result
 }
 
-async fn run<B: Backend>(
+fn run<B: Backend>(
     terminal: &mut Terminal<B>,
     mut group: ProcessGroup,
-) -> anyhow::Result<()> {
+    policy: &ExitPolicy,
+) -> anyhow::Result<u8> {
     loop {
         terminal.draw(|f| group.clone().render(f.area(), f.buffer_mut()))?;
 
-        group = match handle_event(group).await {
-            Ok(Some(group)) => group,
-            Ok(None) => return Ok(()),
+        if let Some(code) = group.check_exit(policy) {
+            return Ok(code);
+        }
+
+        match handle_event(&mut group) {
+            Ok(true) => {}
+            Ok(false) => return Ok(group.exit_code()),
             Err(e) => {
                 eprintln!("Error: {:?}", e);
                 return Err(e);
@@ -177,63 +496,48 @@ async fn run<B: Backend>(
     }
 }
 
-async fn handle_event(mut group: ProcessGroup) -> anyhow::Result<Option<ProcessGroup>> {
+/// Handles a single terminal event, returning `false` once hawk should quit.
+fn handle_event(group: &mut ProcessGroup) -> anyhow::Result<bool> {
     // timeout if an event is not received within `Duration` so we don't block.
     if event::poll(Duration::from_millis(10))? {
         match event::read()? {
-            Event::Key(key) => {
-                if key.kind == KeyEventKind::Press {
-                    match key.code {
-                        KeyCode::Char('c') => {
-                            if matches!(key.modifiers, KeyModifiers::CONTROL) {
-                                return Ok(None);
-                            }
-                        }
-                        KeyCode::Char('q') => return Ok(None),
-                        KeyCode::Char(input) => {
-                            group.handle_input(Bytes::from(input.to_string().into_bytes()))?
-                        }
-                        KeyCode::Backspace => {
-                            group.handle_input(Bytes::from(vec![8]))?;
-                        }
-                        KeyCode::Enter => group.handle_input(Bytes::from(vec![b'\n']))?,
-                        KeyCode::Left => group.prev(),
-                        KeyCode::Right => group.next(),
-                        KeyCode::Up => group.handle_input(Bytes::from(vec![27, 91, 65]))?,
-                        KeyCode::Down => group.handle_input(Bytes::from(vec![27, 91, 66]))?,
-                        KeyCode::Home => todo!(),
-                        KeyCode::End => todo!(),
-                        KeyCode::PageUp => todo!(),
-                        KeyCode::PageDown => todo!(),
-                        KeyCode::Tab => todo!(),
-                        KeyCode::BackTab => todo!(),
-                        KeyCode::Delete => todo!(),
-                        KeyCode::Insert => todo!(),
-                        KeyCode::F(_) => todo!(),
-                        KeyCode::Null => todo!(),
-                        KeyCode::Esc => todo!(),
-                        KeyCode::CapsLock => todo!(),
-                        KeyCode::ScrollLock => todo!(),
-                        KeyCode::NumLock => todo!(),
-                        KeyCode::PrintScreen => todo!(),
-                        KeyCode::Pause => todo!(),
-                        KeyCode::Menu => todo!(),
-                        KeyCode::KeypadBegin => todo!(),
-                        KeyCode::Media(_) => todo!(),
-                        KeyCode::Modifier(_) => todo!(),
-                    }
-                }
+            Event::Key(key) if key.kind == KeyEventKind::Press => {
+                return group.handle_key(key);
             }
-            Event::FocusGained => {}
-            Event::FocusLost => {}
-            Event::Mouse(_) => {}
-            Event::Paste(_) => todo!(),
+            Event::Key(_) | Event::FocusGained | Event::FocusLost => {}
+            Event::Mouse(mouse) => group.handle_mouse(mouse)?,
+            Event::Paste(text) => group.paste(&text),
             Event::Resize(cols, rows) => {
-                group.resize(cols, rows);
+                group.resize(rows, cols);
 
                 // parser.write().set_size(rows, cols);
             }
         }
     }
-    Ok(Some(group))
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_scrollback() {
+        assert_eq!(parse_scrollback("500"), Ok(500));
+        assert_eq!(parse_scrollback("0"), Ok(0));
+        assert_eq!(parse_scrollback("unlimited"), Ok(usize::MAX));
+        assert!(parse_scrollback("-1").is_err());
+        assert!(parse_scrollback("lots").is_err());
+    }
+
+    #[test]
+    fn parses_speed() {
+        assert_eq!(parse_speed("2"), Ok(2.0));
+        assert_eq!(parse_speed("0.5"), Ok(0.5));
+        assert!(parse_speed("0").is_err());
+        assert!(parse_speed("-1").is_err());
+        assert!(parse_speed("inf").is_err());
+        assert!(parse_speed("NaN").is_err());
+        assert!(parse_speed("fast").is_err());
+    }
 }
@@ -1,5 +1,26 @@
+mod bar;
+mod copy;
+mod details;
+mod filter;
 mod group;
+mod history;
+mod layout;
+mod logsearch;
+mod matcher;
+mod merged;
+mod output;
+mod runs;
 mod screen;
+mod search;
+mod sidebar;
+mod status;
+mod usage;
 mod widget;
 
 pub(crate) use group::ProcessGroup;
+pub(crate) use layout::LayoutMode;
+pub(crate) use matcher::Patterns;
+pub(crate) use output::Outputs;
+pub(crate) use runs::{Run, StartedBy};
+pub(crate) use screen::ProcessScreen;
+pub(crate) use status::{format_duration, ExitPolicy};
@@ -0,0 +1,122 @@
+use ratatui::prelude::*;
+
+use super::{screen::ProcessScreen, status::ProcessStatus};
+use crate::list::List;
+
+/// Marks a process with output since it was last focused.
+pub(crate) const UNREAD: &str = "●";
+/// Marks a process that printed an error since it was last focused.
+const ERROR: &str = "!";
+
+/// The markers for anything `process` has done since it was last focused.
+pub(crate) fn markers(process: &ProcessScreen) -> Vec<Span<'static>> {
+    let mut markers = Vec::new();
+    if process.error_count() > 0 {
+        markers.push(Span::styled(
+            ERROR,
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        ));
+    }
+    if process.has_unread() {
+        markers.push(Span::styled(UNREAD, Style::default().fg(Color::Yellow)));
+    }
+    markers
+}
+
+/// One line of tabs, one per process in the colour of its status, highlighting
+/// the focused one and marking the others' activity.
+pub(crate) struct ProcessBar<'a> {
+    blocks: &'a List<ProcessScreen>,
+}
+
+impl<'a> ProcessBar<'a> {
+    pub(crate) fn new(blocks: &'a List<ProcessScreen>) -> Self {
+        Self { blocks }
+    }
+
+    /// The process whose tab is at `column` of a bar rendered into `area`.
+    pub(crate) fn tab_at(&self, area: Rect, column: u16) -> Option<usize> {
+        let mut right = usize::from(area.x);
+        self.tabs().iter().position(|tab| {
+            right += tab.iter().map(Span::width).sum::<usize>();
+            usize::from(column) < right
+        })
+    }
+
+    /// The spans of each tab, including the gap after it.
+    fn tabs(&self) -> Vec<Vec<Span<'static>>> {
+        let focused = self.blocks.focused_index();
+        self.blocks
+            .iter()
+            .enumerate()
+            .map(|(i, block)| {
+                let style = tab_style(&block.status(), i == focused);
+                let mut tab = vec![Span::styled(format!(" {} {}", i + 1, block.name), style)];
+                if i != focused {
+                    tab.extend(markers(block));
+                }
+                tab.push(Span::styled(" ", style));
+                tab.push(Span::raw(" "));
+                tab
+            })
+            .collect()
+    }
+}
+
+/// A tab in the colour of the process's `status`, filled in if it's `focused`.
+fn tab_style(status: &ProcessStatus, focused: bool) -> Style {
+    let color = status.color();
+    if focused {
+        Style::default()
+            .fg(Color::Black)
+            .bg(color)
+            .add_modifier(Modifier::BOLD)
+    } else {
+        Style::default().fg(color)
+    }
+}
+
+impl<'a> Widget for ProcessBar<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer)
+    where
+        Self: Sized,
+    {
+        Line::from(self.tabs().concat()).render(area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use portable_pty::ExitStatus;
+
+    use super::*;
+    use crate::process::status::Limit;
+
+    #[test]
+    fn colours_tabs_by_status() {
+        let colours = [
+            ProcessStatus::Running,
+            ProcessStatus::Exited(ExitStatus::with_exit_code(1), None),
+            ProcessStatus::over_limit(Limit::Cpu),
+            ProcessStatus::Exited(ExitStatus::with_exit_code(0), None),
+            ProcessStatus::Stopped,
+        ]
+        .map(|status| tab_style(&status, false).fg);
+        assert_eq!(
+            [
+                Some(Color::Green),
+                Some(Color::Red),
+                Some(Color::Red),
+                Some(Color::DarkGray),
+                Some(Color::DarkGray)
+            ],
+            colours
+        );
+
+        let focused = tab_style(&ProcessStatus::Running, true);
+        assert_eq!(
+            (Some(Color::Black), Some(Color::Green)),
+            (focused.fg, focused.bg)
+        );
+    }
+}
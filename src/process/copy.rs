@@ -0,0 +1,205 @@
+use super::history::Line;
+
+/// A position in a process's history. `line` counts from the oldest line kept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct Point {
+    pub(crate) line: usize,
+    pub(crate) col: u16,
+}
+
+/// A cursor movement in copy mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Motion {
+    Left,
+    Right,
+    Up,
+    Down,
+    WordForward,
+    WordBackward,
+    LineStart,
+    LineEnd,
+    PageUp,
+    PageDown,
+    Top,
+    Bottom,
+}
+
+/// The cursor and selection of copy mode in a process's history.
+#[derive(Debug, Clone)]
+pub(crate) struct CopyMode {
+    pub(crate) cursor: Point,
+    anchor: Option<Point>,
+    linewise: bool,
+}
+
+impl CopyMode {
+    pub(crate) fn new(cursor: Point) -> Self {
+        Self {
+            cursor,
+            anchor: None,
+            linewise: false,
+        }
+    }
+
+    /// Starts a selection at the cursor, or drops it if one of the same kind is active.
+    pub(crate) fn toggle_selection(&mut self, linewise: bool) {
+        if self.anchor.is_some() && self.linewise == linewise {
+            self.anchor = None;
+        } else {
+            self.anchor.get_or_insert(self.cursor);
+            self.linewise = linewise;
+        }
+    }
+
+    pub(crate) fn has_selection(&self) -> bool {
+        self.anchor
+            .is_some_and(|anchor| anchor != self.cursor || self.linewise)
+    }
+
+    /// Moves the cursor within `lines`, where `page` is the height of the screen.
+    pub(crate) fn motion(&mut self, motion: Motion, lines: &[Line], page: usize, cols: u16) {
+        let last = lines.len().saturating_sub(1);
+        let Point { line, col } = self.cursor;
+        let current = lines.get(line);
+        self.cursor = match motion {
+            Motion::Left => Point {
+                line,
+                col: col.saturating_sub(1),
+            },
+            Motion::Right => Point {
+                line,
+                col: (col + 1).min(cols.saturating_sub(1)),
+            },
+            Motion::Up => Point {
+                line: line.saturating_sub(1),
+                col,
+            },
+            Motion::Down => Point {
+                line: (line + 1).min(last),
+                col,
+            },
+            Motion::WordForward => match current.and_then(|l| l.next_word(col)) {
+                Some(col) => Point { line, col },
+                None if line < last => Point {
+                    line: line + 1,
+                    col: lines[line + 1].first_word().unwrap_or(0),
+                },
+                None => self.cursor,
+            },
+            Motion::WordBackward => match current.and_then(|l| l.prev_word(col)) {
+                Some(col) => Point { line, col },
+                None if line > 0 => Point {
+                    line: line - 1,
+                    col: lines[line - 1].prev_word(u16::MAX).unwrap_or(0),
+                },
+                None => self.cursor,
+            },
+            Motion::LineStart => Point { line, col: 0 },
+            Motion::LineEnd => Point {
+                line,
+                col: current.map_or(0, |l| l.width().saturating_sub(1)),
+            },
+            Motion::PageUp => Point {
+                line: line.saturating_sub(page),
+                col,
+            },
+            Motion::PageDown => Point {
+                line: (line + page).min(last),
+                col,
+            },
+            Motion::Top => Point { line: 0, col: 0 },
+            Motion::Bottom => Point { line: last, col: 0 },
+        };
+    }
+
+    /// Extends the selection (starting one if needed) to `point`, as a mouse drag does.
+    pub(crate) fn drag_to(&mut self, point: Point) {
+        self.anchor.get_or_insert(self.cursor);
+        self.cursor = point;
+    }
+
+    /// The first and last selected points, inclusive, in a screen `cols` wide.
+    fn range(&self, cols: u16) -> Option<(Point, Point)> {
+        let anchor = self.anchor?;
+        let (mut start, mut end) = if anchor <= self.cursor {
+            (anchor, self.cursor)
+        } else {
+            (self.cursor, anchor)
+        };
+        if self.linewise {
+            start.col = 0;
+            end.col = cols.saturating_sub(1);
+        }
+        Some((start, end))
+    }
+
+    pub(crate) fn is_selected(&self, point: Point, cols: u16) -> bool {
+        self.range(cols)
+            .is_some_and(|(start, end)| start <= point && point <= end)
+    }
+
+    /// The selected text, with lines joined by newlines and trailing blanks dropped.
+    pub(crate) fn selected_text(&self, lines: &[Line]) -> String {
+        let Some((start, end)) = self.range(u16::MAX) else {
+            return String::new();
+        };
+        let mut text = Vec::new();
+        for (i, line) in lines.iter().enumerate().take(end.line + 1).skip(start.line) {
+            let from = if i == start.line { start.col } else { 0 };
+            let to = if i == end.line {
+                end.col.saturating_add(1)
+            } else {
+                u16::MAX
+            };
+            text.push(line.slice(from, to));
+        }
+        text.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::history::{history, tests::parser_with_lines};
+
+    #[test]
+    fn selects_across_lines() {
+        let mut parser = parser_with_lines(3);
+        let lines = history(&mut parser);
+        let mut copy = CopyMode::new(Point { line: 0, col: 5 });
+
+        copy.toggle_selection(false);
+        copy.motion(Motion::Down, &lines, 4, 20);
+        copy.motion(Motion::LineStart, &lines, 4, 20);
+
+        assert_eq!("1\nl", copy.selected_text(&lines));
+    }
+
+    #[test]
+    fn linewise_selection() {
+        let mut parser = parser_with_lines(3);
+        let lines = history(&mut parser);
+        let mut copy = CopyMode::new(Point { line: 1, col: 3 });
+
+        copy.toggle_selection(true);
+        copy.motion(Motion::Down, &lines, 4, 20);
+
+        assert_eq!("line 2\nline 3", copy.selected_text(&lines));
+    }
+
+    #[test]
+    fn moves_by_words() {
+        let mut parser = vt100::Parser::new(2, 30, 0);
+        parser.process(b"cargo  run --release\r\nnext");
+        let lines = history(&mut parser);
+        let mut copy = CopyMode::new(Point { line: 0, col: 0 });
+
+        copy.motion(Motion::WordForward, &lines, 2, 30);
+        assert_eq!(Point { line: 0, col: 7 }, copy.cursor);
+        copy.motion(Motion::WordForward, &lines, 2, 30);
+        copy.motion(Motion::WordForward, &lines, 2, 30);
+        assert_eq!(Point { line: 1, col: 0 }, copy.cursor);
+        copy.motion(Motion::WordBackward, &lines, 2, 30);
+        assert_eq!(Point { line: 0, col: 11 }, copy.cursor);
+    }
+}
@@ -0,0 +1,99 @@
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Clear, Paragraph},
+};
+
+use super::{screen::ProcessScreen, status::format_duration};
+use crate::logs::clock;
+
+const WIDTH: u16 = 72;
+
+/// A popup with a process's current run and the runs before it.
+pub(crate) struct Details<'a> {
+    process: &'a ProcessScreen,
+}
+
+impl<'a> Details<'a> {
+    pub(crate) fn new(process: &'a ProcessScreen) -> Self {
+        Self { process }
+    }
+
+    fn lines(&self) -> Vec<Line<'static>> {
+        let label = |text: &str| Span::styled(format!("{:10}", text), Style::default().bold());
+        let process = self.process;
+        let status = process.status();
+
+        let mut state = status.state().to_owned();
+        if let Some(pid) = process.pid() {
+            state.push_str(&format!(", pid {}", pid));
+        }
+        if let Some(exit_status) = status.exit_status() {
+            state.push_str(&format!(", {}", exit_status));
+        }
+        let mut started = format!(
+            "{} by {}",
+            clock(process.started_at()),
+            process.started_by()
+        );
+        if let Some(uptime) = process.uptime() {
+            started.push_str(&format!(" (up {})", format_duration(uptime)));
+        }
+        let mut lines = vec![
+            Line::from(vec![label("state"), Span::raw(state)]),
+            Line::from(vec![label("started"), Span::raw(started)]),
+            Line::from(vec![
+                label("restarts"),
+                Span::raw(process.restarts().to_string()),
+            ]),
+        ];
+        if let Some(usage) = process.usage() {
+            lines.push(Line::from(vec![label("usage"), Span::raw(usage.summary())]));
+        }
+
+        lines.push(Line::default());
+        if process.runs().is_empty() {
+            lines.push(Line::from("No previous runs"));
+        } else {
+            lines.push(Line::from(Span::styled(
+                "Previous runs, newest first",
+                Style::default().bold(),
+            )));
+        }
+        for run in process.runs().iter().rev() {
+            lines.push(Line::from(format!(
+                "{}  {:6}  ran {:7}  {}",
+                clock(run.started),
+                run.started_by,
+                format_duration(run.duration),
+                run.ended
+            )));
+        }
+        lines
+    }
+}
+
+impl<'a> Widget for Details<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer)
+    where
+        Self: Sized,
+    {
+        let lines = self.lines();
+        let height = u16::try_from(lines.len() + 2).unwrap_or(u16::MAX);
+        let [popup] = Layout::horizontal([Constraint::Length(WIDTH)])
+            .flex(layout::Flex::Center)
+            .areas(area);
+        let [popup] = Layout::vertical([Constraint::Length(height)])
+            .flex(layout::Flex::Center)
+            .areas(popup);
+
+        Clear.render(popup, buf);
+        Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Cyan))
+                    .title(format!(" {} ", self.process.name)),
+            )
+            .render(popup, buf);
+    }
+}
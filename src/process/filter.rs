@@ -0,0 +1,195 @@
+use std::{collections::VecDeque, sync::Arc};
+
+use parking_lot::RwLock;
+use regex::Regex;
+
+/// A process's recent output as lines of plain text, built from the bytes it
+/// writes rather than its screen, so long lines aren't split where they wrapped.
+#[derive(Debug, Clone)]
+pub(crate) struct TextLines {
+    inner: Arc<RwLock<Lines>>,
+    capacity: usize,
+}
+
+#[derive(Debug, Default)]
+struct Lines {
+    lines: VecDeque<String>,
+    /// Lines pushed in all, so the number of the next one.
+    pushed: usize,
+    /// The lines the last filter let through, kept up to date as lines come
+    /// and go so it only checks each line once.
+    matches: Option<Matches>,
+}
+
+#[derive(Debug)]
+struct Matches {
+    query: String,
+    /// The numbers of the matching lines, oldest first.
+    lines: VecDeque<usize>,
+    /// Lines checked so far, by number.
+    checked: usize,
+}
+
+impl TextLines {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            inner: Arc::default(),
+            capacity,
+        }
+    }
+
+    pub(crate) fn push(&self, line: &str) {
+        let mut inner = self.inner.write();
+        if inner.lines.len() >= self.capacity {
+            inner.lines.pop_front();
+        }
+        inner.lines.push_back(line.to_owned());
+        inner.pushed += 1;
+    }
+
+    /// The last `count` lines `filter` lets through, oldest first, and how many
+    /// it lets through in all.
+    pub(crate) fn filtered(&self, filter: &Filter, count: usize) -> (Vec<String>, usize) {
+        let mut inner = self.inner.write();
+        let Lines {
+            lines,
+            pushed,
+            matches,
+        } = &mut *inner;
+        let first = *pushed - lines.len();
+        let matches = match matches {
+            Some(matches) if matches.query == filter.query => matches,
+            _ => matches.insert(Matches {
+                query: filter.query.clone(),
+                lines: VecDeque::new(),
+                checked: first,
+            }),
+        };
+        while matches.lines.front().is_some_and(|line| *line < first) {
+            matches.lines.pop_front();
+        }
+        let unchecked = matches.checked.max(first)..*pushed;
+        matches.lines.extend(
+            unchecked
+                .clone()
+                .filter(|number| filter.is_match(&lines[number - first])),
+        );
+        matches.checked = unchecked.end;
+
+        let mut shown: Vec<String> = matches
+            .lines
+            .iter()
+            .rev()
+            .take(count)
+            .map(|number| lines[number - first].clone())
+            .collect();
+        shown.reverse();
+        (shown, matches.lines.len())
+    }
+}
+
+/// Shows only the lines of a pane's output that match a regex, or with a
+/// leading `!`, only those that don't.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Filter {
+    query: String,
+    regex: Option<Regex>,
+    invert: bool,
+    error: Option<String>,
+}
+
+impl Filter {
+    pub(crate) fn new(query: &str) -> Self {
+        let (invert, pattern) = query
+            .strip_prefix('!')
+            .map_or((false, query), |pattern| (true, pattern));
+        let (regex, error) = match Regex::new(pattern) {
+            Ok(regex) => (Some(regex), None),
+            Err(e) => (None, Some(e.to_string())),
+        };
+        Self {
+            query: query.to_owned(),
+            regex,
+            invert,
+            error,
+        }
+    }
+
+    pub(crate) fn query(&self) -> &str {
+        &self.query
+    }
+
+    /// Whether `line` is shown. Every line is while the regex is invalid.
+    pub(crate) fn is_match(&self, line: &str) -> bool {
+        self.regex
+            .as_ref()
+            .map_or(true, |regex| regex.is_match(line) != self.invert)
+    }
+
+    /// A short description of the filter for the pane header.
+    pub(crate) fn summary(&self) -> String {
+        if self.error.is_some() {
+            return format!("filter {} (invalid regex)", self.query);
+        }
+        let pattern = self.regex.as_ref().map_or("", Regex::as_str);
+        if self.invert {
+            format!("filter !/{}/", pattern)
+        } else {
+            format!("filter /{}/", pattern)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filters_lines() {
+        let lines = TextLines::new(10);
+        for line in ["GET /", "GET /health", "POST /login", "GET /health"] {
+            lines.push(line);
+        }
+
+        let (shown, total) = lines.filtered(&Filter::new("health"), 1);
+        assert_eq!((shown, total), (vec!["GET /health".to_owned()], 2));
+
+        let (shown, total) = lines.filtered(&Filter::new("!health"), 10);
+        assert_eq!(
+            (shown, total),
+            (vec!["GET /".to_owned(), "POST /login".to_owned()], 2)
+        );
+
+        let invalid = Filter::new("(");
+        assert_eq!(lines.filtered(&invalid, 10).1, 4);
+        assert!(invalid.is_match("anything"));
+        assert_eq!(invalid.summary(), "filter ( (invalid regex)");
+    }
+
+    #[test]
+    fn keeps_matches_up_to_date() {
+        let lines = TextLines::new(3);
+        let health = Filter::new("health");
+        lines.push("GET /health");
+        lines.push("GET /");
+        assert_eq!(lines.filtered(&health, 10).1, 1);
+
+        lines.push("GET /health");
+        lines.push("POST /login");
+        let (shown, total) = lines.filtered(&health, 10);
+        assert_eq!((shown, total), (vec!["GET /health".to_owned()], 1));
+
+        lines.push("GET /health?full");
+        let (shown, total) = lines.filtered(&health, 10);
+        assert_eq!(
+            (shown, total),
+            (
+                vec!["GET /health".to_owned(), "GET /health?full".to_owned()],
+                2
+            )
+        );
+
+        let (shown, total) = lines.filtered(&Filter::new("login"), 10);
+        assert_eq!((shown, total), (vec!["POST /login".to_owned()], 1));
+    }
+}
@@ -1,20 +1,121 @@
-use std::sync::Arc;
+use std::{
+    collections::{HashMap, HashSet},
+    io,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use bytes::Bytes;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
 use parking_lot::RwLock;
 use portable_pty::CommandBuilder;
 use ratatui::{prelude::*, widgets::Paragraph};
-use tokio::sync::mpsc::error::SendError;
+use tokio::{
+    task,
+    time::{self, MissedTickBehavior},
+};
 
-use crate::list::List;
+use crate::{
+    clipboard,
+    config::{Config, HighlightRule, LayoutConfig, Limits},
+    events::EventBus,
+    keymap::{Action, Keymap},
+    list::List,
+};
 
-use super::{screen::ProcessScreen, widget::ProcessWidget};
+use super::{
+    bar::ProcessBar,
+    copy::Motion,
+    details::Details,
+    layout::{self, LayoutMode},
+    logsearch::{self, BackgroundSearch, LogSearch},
+    merged::{MergedLog, MergedView, MergedWidget},
+    output::Outputs,
+    runs::StartedBy,
+    screen::{ProcessScreen, KILL_GRACE},
+    sidebar::{self, Sidebar},
+    status::{self, ExitPolicy, Limit},
+    usage::UsageSampler,
+    widget::ProcessWidget,
+};
+
+/// Lines scrolled per mouse wheel notch when the child doesn't handle the mouse.
+const WHEEL_LINES: isize = 3;
+
+/// How often a process being ended is checked for having exited.
+const EXIT_POLL: Duration = Duration::from_millis(50);
+
+/// Where each part of the screen goes.
+#[derive(Debug, Default)]
+struct Areas {
+    /// Where the panes (or the merged view) go.
+    main: Rect,
+    /// One per process, empty for those that aren't shown.
+    panes: Vec<Rect>,
+    /// The sidebar, empty when it's hidden.
+    sidebar: Rect,
+    /// The process bar, empty when every process has a pane.
+    bar: Rect,
+    footer: Rect,
+}
+
+/// What keyboard input is currently driving.
+#[derive(Debug, Clone, Default)]
+enum Mode {
+    /// Keys go to the focused process.
+    #[default]
+    Normal,
+    /// The prefix key was pressed, so the next key is a command for hawk.
+    Prefix,
+    /// Keys scroll the focused process's history.
+    Scroll,
+    /// Keys edit or navigate a search of the focused process's history.
+    Search { query: String, editing: bool },
+    /// The lines of the focused process's log matching the search are shown
+    /// until a key goes back to the search.
+    LogSearch {
+        query: String,
+        search: BackgroundSearch,
+    },
+    /// Keys move a cursor and selection around the focused process's history.
+    Copy,
+    /// Every process's output is shown interleaved, and keys scroll and filter it.
+    Logs,
+    /// Keys edit the regex the focused pane's lines are filtered by.
+    Filter { query: String },
+    /// The focused process's details are shown until a key closes them.
+    Details,
+}
 
 #[derive(Clone)]
 pub(crate) struct ProcessGroup {
     blocks: Arc<RwLock<List<ProcessScreen>>>,
     rows: u16,
     cols: u16,
+    scrollback: usize,
+    keymap: Keymap,
+    events: EventBus,
+    /// Every process's lines, for the merged view.
+    merged: MergedLog,
+    merged_view: MergedView,
+    highlights: Arc<[HighlightRule]>,
+    limits: Arc<HashMap<String, Limits>>,
+    mode: Mode,
+    layout: LayoutMode,
+    /// Where the panes go when they're tiled.
+    tiling: Arc<LayoutConfig>,
+    /// The processes that don't get a pane when they're tiled.
+    hidden: HashSet<String>,
+    /// Whether the focused pane fills the screen.
+    zoomed: bool,
+    /// Whether the process list is shown beside the panes.
+    sidebar: bool,
+    /// Whether recent CPU use is drawn beside the current use.
+    sparklines: bool,
+    /// Whether input goes to the marked processes, or all of them if none are marked.
+    broadcast: bool,
+    /// The pane a mouse selection is being dragged in.
+    dragging: Option<usize>,
     sized: bool,
 }
 
@@ -29,29 +130,718 @@ impl ProcessGroup {
         blocks.prev();
     }
 
-    pub(crate) fn new(rows: u16, cols: u16) -> Self {
+    pub(crate) fn new(
+        rows: u16,
+        cols: u16,
+        scrollback: usize,
+        keymap: Keymap,
+        events: EventBus,
+        config: &Config,
+        layout: LayoutMode,
+    ) -> Self {
         let blocks = Arc::new(RwLock::new(List::<ProcessScreen>::new()));
         Self {
             blocks,
             rows,
             cols,
+            scrollback,
+            keymap,
+            events,
+            merged: MergedLog::new(scrollback),
+            merged_view: MergedView::default(),
+            highlights: config.highlight.clone().into(),
+            limits: Arc::new(config.limits.clone()),
+            mode: Mode::default(),
+            layout,
+            tiling: Arc::new(config.layout.clone()),
+            hidden: config.layout.hidden.iter().cloned().collect(),
+            zoomed: false,
+            sidebar: false,
+            sparklines: false,
+            broadcast: false,
+            dragging: None,
             sized: false,
         }
     }
 
-    pub(crate) fn add(&mut self, name: &str, cmd: CommandBuilder) -> anyhow::Result<()> {
-        let block = ProcessScreen::new(name.to_owned(), cmd, self.rows, self.cols)?;
+    pub(crate) fn show_sparklines(&mut self, sparklines: bool) {
+        self.sparklines = sparklines;
+    }
+
+    /// Samples every process's CPU and memory use each `period` from now on,
+    /// killing any that go over their memory limit.
+    pub(crate) fn sample_usage(&self, period: Duration) -> task::JoinHandle<()> {
+        let blocks = self.blocks.clone();
+        let limits = self.limits.clone();
+        tokio::spawn(async move {
+            let mut sampler = UsageSampler::default();
+            let mut ticks = time::interval(period);
+            ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            loop {
+                ticks.tick().await;
+                let processes: Vec<_> = blocks
+                    .read()
+                    .iter()
+                    .map(|block| (block.pid(), block.usage.clone()))
+                    .collect();
+                // Reading every process's stats from /proc blocks, so it's kept
+                // off the workers the ptys and the control socket share.
+                let sampled = task::spawn_blocking(move || {
+                    sampler.sample(&processes);
+                    sampler
+                });
+                let Ok(sampled) = sampled.await else {
+                    break;
+                };
+                sampler = sampled;
+                for block in blocks.read().iter() {
+                    let memory = limits.get(&block.name).and_then(|limits| limits.memory);
+                    let usage = block.usage();
+                    if let (Some(memory), Some(usage)) = (memory, usage) {
+                        if usage.rss > memory {
+                            // It's sampled until it has exited, so a kill that
+                            // fails to send is tried again at the next sample.
+                            let _ = block.kill_over_limit(Limit::Memory(memory));
+                        }
+                    }
+                }
+            }
+        })
+    }
+
+    pub(crate) fn add(
+        &mut self,
+        name: &str,
+        cmd: CommandBuilder,
+        outputs: Outputs,
+    ) -> anyhow::Result<()> {
+        let outputs = Outputs {
+            merged: Some(self.merged.source(self.blocks.read().len())),
+            highlights: self.highlights.clone(),
+            ..outputs
+        };
+        let block = ProcessScreen::new(
+            name.to_owned(),
+            cmd,
+            self.rows,
+            self.cols,
+            self.scrollback,
+            self.events.clone(),
+            outputs,
+        )?;
         let mut blocks = self.blocks.write();
         blocks.add(block);
         Ok(())
     }
 
-    pub(crate) fn handle_input(&self, input: Bytes) -> Result<(), SendError<Bytes>> {
-        let blocks = self.blocks.write();
-        blocks.focused().handle_input(input);
+    /// Handles a key press, returning `false` once hawk should quit. Keys go to
+    /// the focused process unless they follow the prefix key or a mode has them.
+    pub(crate) fn handle_key(&mut self, key: KeyEvent) -> anyhow::Result<bool> {
+        match self.mode {
+            Mode::Search { .. } => self.handle_search_key(key),
+            Mode::Copy => self.handle_copy_key(key)?,
+            Mode::Scroll => self.handle_scroll_key(key),
+            Mode::Logs => self.handle_logs_key(key),
+            Mode::Filter { .. } => self.handle_filter_key(key),
+            Mode::Details => {
+                if matches!(key.code, KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q')) {
+                    self.mode = Mode::Normal;
+                }
+            }
+            Mode::LogSearch { ref mut query, .. } => {
+                if matches!(
+                    key.code,
+                    KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q' | 'l')
+                ) {
+                    self.mode = Mode::Search {
+                        query: std::mem::take(query),
+                        editing: false,
+                    };
+                }
+            }
+            Mode::Prefix => {
+                self.mode = Mode::Normal;
+                // Pressing the prefix twice sends it on to the process.
+                if self.keymap.prefix.matches(key) {
+                    self.handle_input(|block| block.handle_key(key));
+                } else if let Some(action) = self.keymap.command(key) {
+                    return self.perform(action);
+                }
+            }
+            Mode::Normal if self.keymap.prefix.matches(key) => self.mode = Mode::Prefix,
+            Mode::Normal => match self.keymap.root(key) {
+                Some(action) => return self.perform(action),
+                None => self.handle_input(|block| block.handle_key(key)),
+            },
+        }
+        Ok(true)
+    }
+
+    /// Adds pasted text to the search query being typed, or sends it to the focused process.
+    pub(crate) fn paste(&mut self, text: &str) {
+        let focused = self.blocks.read().focused();
+        match &mut self.mode {
+            Mode::Search {
+                query,
+                editing: true,
+            } => {
+                query.extend(text.chars().filter(|c| !c.is_control()));
+                focused.search(query);
+            }
+            Mode::Filter { query } => {
+                query.extend(text.chars().filter(|c| !c.is_control()));
+                focused.set_filter(Some(query));
+            }
+            Mode::Normal => self.handle_input(|block| block.paste(text)),
+            Mode::Prefix
+            | Mode::Scroll
+            | Mode::Copy
+            | Mode::Logs
+            | Mode::Details
+            | Mode::Search { .. }
+            | Mode::LogSearch { .. } => {}
+        }
+    }
+
+    /// Sends input to the focused process or, while broadcasting, to every target.
+    fn handle_input(&self, send: impl Fn(&ProcessScreen)) {
+        let blocks = self.blocks.read();
+        if !self.broadcast {
+            send(&blocks.focused());
+            return;
+        }
+        let targets = Self::broadcast_targets(&blocks);
+        for (block, _) in blocks.iter().zip(targets).filter(|(_, target)| *target) {
+            send(block);
+        }
+    }
+
+    /// Which processes broadcast input reaches.
+    fn broadcast_targets(blocks: &List<ProcessScreen>) -> Vec<bool> {
+        let marked: Vec<_> = blocks.iter().map(|block| block.marked).collect();
+        marked_or_all(&marked)
+    }
+
+    fn toggle_mark(&self) {
+        let mut blocks = self.blocks.write();
+        let index = blocks.focused_index();
+        if let Some(block) = blocks.get_mut(index) {
+            block.marked = !block.marked;
+        }
+    }
+
+    /// Carries out one of hawk's commands, returning `false` once hawk should quit.
+    fn perform(&mut self, action: Action) -> anyhow::Result<bool> {
+        match action {
+            Action::Quit => return Ok(false),
+            Action::FocusNext => self.next(),
+            Action::FocusPrev => self.prev(),
+            Action::Restart => self.restart_focused()?,
+            Action::Zoom => self.toggle_zoom(),
+            Action::ScrollUp => self.start_scroll(1),
+            Action::ScrollDown => self.start_scroll(-1),
+            Action::Search => self.start_search(),
+            Action::Copy => self.start_copy(),
+            Action::Broadcast => self.broadcast = !self.broadcast,
+            Action::Mark => self.toggle_mark(),
+            Action::Logs => self.mode = Mode::Logs,
+            Action::Filter => self.start_filter(),
+            Action::Sidebar => self.toggle_sidebar(),
+            Action::Layout => self.toggle_layout(),
+            Action::Hide => self.toggle_hidden(),
+            Action::Details => self.mode = Mode::Details,
+        }
+        Ok(true)
+    }
+
+    fn restart_focused(&self) -> anyhow::Result<()> {
+        let blocks = self.blocks.read();
+        let Some(name) = blocks
+            .get(blocks.focused_index())
+            .map(|block| block.name.clone())
+        else {
+            return Ok(());
+        };
+        drop(blocks);
+        self.restart_later(&name, StartedBy::Key)
+    }
+
+    /// Restarts the process called `name` in the background, running it again
+    /// only once its current run has exited so the two never overlap.
+    pub(crate) fn restart_later(&self, name: &str, by: StartedBy) -> anyhow::Result<()> {
+        self.with_process(name, |process| Ok(process.end_run()?))?;
+        let group = self.clone();
+        let name = name.to_owned();
+        tokio::spawn(async move {
+            // A process that can't be started again just stays exited.
+            let _ = group.finish_restart(&name, by).await;
+        });
+        Ok(())
+    }
+
+    /// Restarts the process called `name`, returning once its new run has
+    /// started, which can take as long as [`Self::wait_for_exit`] does.
+    pub(crate) async fn restart(&self, name: &str, by: StartedBy) -> anyhow::Result<()> {
+        self.with_process(name, |process| Ok(process.end_run()?))?;
+        self.finish_restart(name, by).await
+    }
+
+    /// Runs the process called `name` again once the run being ended has exited.
+    async fn finish_restart(&self, name: &str, by: StartedBy) -> anyhow::Result<()> {
+        self.wait_for_exit(name).await;
+        self.with_process(name, |process| process.restart(by))
+    }
+
+    /// Waits for the process called `name` to stop running, which after it's
+    /// been ended takes at most about [`KILL_GRACE`], when it's killed outright.
+    pub(crate) async fn wait_for_exit(&self, name: &str) {
+        let deadline = Instant::now() + KILL_GRACE + Duration::from_secs(1);
+        let running = || {
+            self.with_process(name, |process| Ok(process.status().is_running()))
+                .unwrap_or_default()
+        };
+        while running() && Instant::now() < deadline {
+            time::sleep(EXIT_POLL).await;
+        }
+    }
+
+    /// Gives the focused pane the whole screen, or goes back to the grid.
+    fn toggle_zoom(&mut self) {
+        self.zoomed = !self.zoomed;
+        for block in self.blocks.write().iter_mut() {
+            block.recalculate_size();
+        }
+    }
+
+    /// Shows or hides the process list, which takes room from the panes.
+    fn toggle_sidebar(&mut self) {
+        self.sidebar = !self.sidebar;
+        for block in self.blocks.write().iter_mut() {
+            block.recalculate_size();
+        }
+    }
+
+    /// Switches between tiling the panes and showing one at a time in tabs.
+    fn toggle_layout(&mut self) {
+        self.layout = self.layout.toggled();
+        for block in self.blocks.write().iter_mut() {
+            block.recalculate_size();
+        }
+    }
+
+    /// Hides the focused process's pane, or shows it again.
+    fn toggle_hidden(&mut self) {
+        let mut blocks = self.blocks.write();
+        let name = &blocks.focused().name;
+        if !self.hidden.remove(name) {
+            self.hidden.insert(name.clone());
+        }
+        for block in blocks.iter_mut() {
+            block.recalculate_size();
+        }
+    }
+
+    /// Focuses the process whose sidebar entry was clicked, or zooms it if it
+    /// already had focus.
+    fn select(&mut self, index: usize) {
+        let mut blocks = self.blocks.write();
+        if index >= blocks.len() {
+            return;
+        }
+        if index == blocks.focused_index() {
+            drop(blocks);
+            self.toggle_zoom();
+        } else {
+            blocks.focus(index);
+        }
+    }
+
+    /// Scrolls the focused process by `pages` and lets the keys keep scrolling.
+    fn start_scroll(&mut self, pages: isize) {
+        self.blocks.read().focused().scroll_page(pages);
+        self.mode = Mode::Scroll;
+    }
+
+    /// Scrolls through the focused process's history until Esc or q.
+    fn handle_scroll_key(&mut self, key: KeyEvent) {
+        let focused = self.blocks.read().focused();
+        match key.code {
+            KeyCode::Char('k') | KeyCode::Up => focused.scroll(1),
+            KeyCode::Char('j') | KeyCode::Down => focused.scroll(-1),
+            KeyCode::Char('b') | KeyCode::PageUp => focused.scroll_page(1),
+            KeyCode::Char('f' | ' ') | KeyCode::PageDown => focused.scroll_page(-1),
+            KeyCode::Char('g') | KeyCode::Home => focused.scroll_to_top(),
+            KeyCode::Char('G') | KeyCode::End => focused.scroll_to_bottom(),
+            KeyCode::Esc | KeyCode::Char('q') => {
+                focused.scroll_to_bottom();
+                self.mode = Mode::Normal;
+            }
+            _ => {}
+        }
+    }
+
+    /// Scrolls the merged view and shows or hides processes in it, by number,
+    /// until Esc or q.
+    fn handle_logs_key(&mut self, key: KeyEvent) {
+        let page = isize::try_from(self.rows.saturating_sub(3)).unwrap_or(1);
+        let view = &mut self.merged_view;
+        match key.code {
+            KeyCode::Char('k') | KeyCode::Up => view.scroll(1, &self.merged),
+            KeyCode::Char('j') | KeyCode::Down => view.scroll(-1, &self.merged),
+            KeyCode::Char('b') | KeyCode::PageUp => view.scroll(page, &self.merged),
+            KeyCode::Char('f' | ' ') | KeyCode::PageDown => view.scroll(-page, &self.merged),
+            KeyCode::Char('g') | KeyCode::Home => view.scroll_to_top(&self.merged),
+            KeyCode::Char('G') | KeyCode::End => view.scroll_to_bottom(),
+            KeyCode::Char('a') => view.show_all(),
+            KeyCode::Char(c @ '1'..='9') => {
+                let process = usize::from(c as u8 - b'1');
+                if process < self.blocks.read().len() {
+                    view.toggle(process);
+                }
+            }
+            KeyCode::Esc | KeyCode::Char('q') => {
+                view.scroll_to_bottom();
+                self.mode = Mode::Normal;
+            }
+            _ => {}
+        }
+    }
+
+    /// Starts editing the focused pane's filter, picking up where it was left.
+    fn start_filter(&mut self) {
+        let query = self.blocks.read().focused().filter_query();
+        self.mode = Mode::Filter {
+            query: query.unwrap_or_default(),
+        };
+    }
+
+    /// Edits the filter, applying it as it's typed. Enter keeps it, and Esc (or
+    /// an empty filter) shows the whole screen again.
+    fn handle_filter_key(&mut self, key: KeyEvent) {
+        let focused = self.blocks.read().focused();
+        let Mode::Filter { query } = &mut self.mode else {
+            return;
+        };
+        match key.code {
+            KeyCode::Esc => {
+                focused.set_filter(None);
+                self.mode = Mode::Normal;
+                return;
+            }
+            KeyCode::Enter => {
+                self.mode = Mode::Normal;
+                return;
+            }
+            KeyCode::Backspace => {
+                query.pop();
+            }
+            KeyCode::Char(c) => query.push(c),
+            _ => return,
+        }
+        focused.set_filter(Some(query.as_str()).filter(|query| !query.is_empty()));
+    }
+
+    fn start_search(&mut self) {
+        self.mode = Mode::Search {
+            query: String::new(),
+            editing: true,
+        };
+    }
+
+    /// Edits the search query while it's being typed, then navigates between matches.
+    fn handle_search_key(&mut self, key: KeyEvent) {
+        let focused = self.blocks.read().focused();
+        let Mode::Search { query, editing } = &mut self.mode else {
+            return;
+        };
+        match key.code {
+            KeyCode::Esc => self.end_search(),
+            KeyCode::Enter if *editing => *editing = false,
+            KeyCode::Backspace if *editing => {
+                query.pop();
+                focused.search(query);
+            }
+            KeyCode::Char(c) if *editing => {
+                query.push(c);
+                focused.search(query);
+            }
+            KeyCode::Char('n') | KeyCode::Down => focused.search_step(true),
+            KeyCode::Char('N') | KeyCode::Up => focused.search_step(false),
+            KeyCode::Char('/') => *editing = true,
+            KeyCode::Char('l') => {
+                if let Some(search) = focused.search_log(logsearch::MAX_MATCHES) {
+                    self.mode = Mode::LogSearch {
+                        query: std::mem::take(query),
+                        search,
+                    };
+                }
+            }
+            KeyCode::Char('q') => self.end_search(),
+            _ => {}
+        }
+    }
+
+    fn end_search(&mut self) {
+        self.mode = Mode::Normal;
+        for block in self.blocks.read().iter() {
+            if block.search_summary().is_some() {
+                block.end_search();
+            }
+        }
+    }
+
+    fn start_copy(&mut self) {
+        self.blocks.read().focused().start_copy();
+        self.mode = Mode::Copy;
+    }
+
+    /// Moves the copy mode cursor and selection, yanking the selection to the clipboard.
+    fn handle_copy_key(&mut self, key: KeyEvent) -> io::Result<()> {
+        let focused = self.blocks.read().focused();
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let motion = match key.code {
+            KeyCode::Char('h') | KeyCode::Left => Motion::Left,
+            KeyCode::Char('l') | KeyCode::Right => Motion::Right,
+            KeyCode::Char('k') | KeyCode::Up => Motion::Up,
+            KeyCode::Char('j') | KeyCode::Down => Motion::Down,
+            KeyCode::Char('w') => Motion::WordForward,
+            KeyCode::Char('b') if ctrl => Motion::PageUp,
+            KeyCode::Char('b') => Motion::WordBackward,
+            KeyCode::Char('0') | KeyCode::Home => Motion::LineStart,
+            KeyCode::Char('$') | KeyCode::End => Motion::LineEnd,
+            KeyCode::Char('f') if ctrl => Motion::PageDown,
+            KeyCode::PageUp => Motion::PageUp,
+            KeyCode::PageDown => Motion::PageDown,
+            KeyCode::Char('g') => Motion::Top,
+            KeyCode::Char('G') => Motion::Bottom,
+            KeyCode::Char('v') => {
+                focused.copy_toggle_selection(false);
+                return Ok(());
+            }
+            KeyCode::Char('V') => {
+                focused.copy_toggle_selection(true);
+                return Ok(());
+            }
+            KeyCode::Char('y') | KeyCode::Enter => {
+                self.mode = Mode::Normal;
+                if let Some(text) = focused.end_copy() {
+                    clipboard::copy(&text)?;
+                }
+                return Ok(());
+            }
+            KeyCode::Esc | KeyCode::Char('q') => {
+                self.mode = Mode::Normal;
+                focused.end_copy();
+                return Ok(());
+            }
+            _ => return Ok(()),
+        };
+        focused.copy_motion(motion);
+        Ok(())
+    }
+
+    /// Focuses the pane that was clicked and scrolls the one under the wheel,
+    /// unless its child has asked for mouse reports, which are forwarded instead.
+    /// Dragging selects text that's copied to the clipboard on release.
+    pub(crate) fn handle_mouse(&mut self, event: MouseEvent) -> io::Result<()> {
+        if matches!(self.mode, Mode::Logs) {
+            match event.kind {
+                MouseEventKind::ScrollUp => self.merged_view.scroll(WHEEL_LINES, &self.merged),
+                MouseEventKind::ScrollDown => self.merged_view.scroll(-WHEEL_LINES, &self.merged),
+                _ => {}
+            }
+            return Ok(());
+        }
+        if let Some(index) = self.dragging {
+            return self.handle_drag(index, event);
+        }
+
+        let position = Position::new(event.column, event.row);
+        let Areas { sidebar, bar, .. } = self.layout(Rect::new(0, 0, self.cols, self.rows));
+        if bar.contains(position) {
+            if event.kind == MouseEventKind::Down(MouseButton::Left) {
+                let mut blocks = self.blocks.write();
+                if let Some(index) = ProcessBar::new(&blocks).tab_at(bar, event.column) {
+                    blocks.focus(index);
+                }
+            }
+            return Ok(());
+        }
+        if sidebar.contains(position) {
+            let focused = self.blocks.read().focused_index();
+            let entry = Sidebar::entry_at(sidebar, focused, event.row);
+            if let (Some(index), MouseEventKind::Down(MouseButton::Left)) = (entry, event.kind) {
+                self.select(index);
+            }
+            return Ok(());
+        }
+        let Some((index, pane)) = self.pane_at(position) else {
+            return Ok(());
+        };
+
+        let mut blocks = self.blocks.write();
+        if matches!(event.kind, MouseEventKind::Down(_)) {
+            blocks.focus(index);
+        }
+        let Some(block) = blocks.get(index) else {
+            return Ok(());
+        };
+
+        let screen = ProcessWidget::screen_area(pane);
+        if screen.contains(position) {
+            let relative = MouseEvent {
+                column: event.column - screen.x,
+                row: event.row - screen.y,
+                ..event
+            };
+            if let Some(report) = block.encode_mouse(relative) {
+                block.handle_input(Bytes::from(report));
+                return Ok(());
+            }
+            if relative.kind == MouseEventKind::Down(MouseButton::Left) {
+                block.start_copy_at(relative.row, relative.column);
+                self.dragging = Some(index);
+                self.mode = Mode::Normal;
+                return Ok(());
+            }
+        }
+
+        match event.kind {
+            MouseEventKind::ScrollUp => block.scroll(WHEEL_LINES),
+            MouseEventKind::ScrollDown => block.scroll(-WHEEL_LINES),
+            _ => {}
+        }
         Ok(())
     }
 
+    fn handle_drag(&mut self, index: usize, event: MouseEvent) -> io::Result<()> {
+        let area = Rect::new(0, 0, self.cols, self.rows);
+        let panes = self.layout(area).panes;
+        let (Some(pane), Some(block)) = (panes.get(index), self.blocks.read().get(index).cloned())
+        else {
+            self.dragging = None;
+            return Ok(());
+        };
+
+        let screen = ProcessWidget::screen_area(*pane);
+        let column = event
+            .column
+            .clamp(screen.left(), screen.right().saturating_sub(1));
+        let row = event
+            .row
+            .clamp(screen.top(), screen.bottom().saturating_sub(1));
+        block.drag_select(row - screen.y, column - screen.x);
+
+        if let MouseEventKind::Up(_) = event.kind {
+            self.dragging = None;
+            if let Some(text) = block.end_copy() {
+                clipboard::copy(&text)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Finds the pane under `position`, along with the area it was rendered into.
+    fn pane_at(&self, position: Position) -> Option<(usize, Rect)> {
+        let area = Rect::new(0, 0, self.cols, self.rows);
+        self.layout(area)
+            .panes
+            .into_iter()
+            .enumerate()
+            .find(|(_, pane)| pane.contains(position))
+    }
+
+    /// Splits `area` into one pane per process and the footer. Panes are tiled as
+    /// the settings say, unless zoomed or showing tabs, when the focused one gets
+    /// all the space. Whenever a process has no pane, a bar lists them all.
+    fn layout(&self, area: Rect) -> Areas {
+        let blocks = self.blocks.read();
+        let [main, footer] = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Fill(1), Constraint::Length(1)].as_ref())
+            .areas(area);
+        let [sidebar, main] = if self.sidebar && !matches!(self.mode, Mode::Logs) {
+            Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Length(sidebar::WIDTH), Constraint::Fill(1)])
+                .areas(main)
+        } else {
+            [Rect::default(), main]
+        };
+
+        let names: Vec<&str> = blocks.iter().map(|block| block.name.as_str()).collect();
+        let single = self.zoomed || self.layout == LayoutMode::Tabs;
+        let shown: Vec<bool> = names
+            .iter()
+            .map(|name| !single && !self.hidden.contains(*name) && self.tiling.places(name))
+            .collect();
+        let [bar, panes_area] = if !names.is_empty() && shown.contains(&false) {
+            Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(1), Constraint::Fill(1)].as_ref())
+                .areas(main)
+        } else {
+            [Rect::default(), main]
+        };
+        let panes = if single {
+            let focused = blocks.focused_index();
+            (0..names.len())
+                .map(|i| {
+                    if i == focused {
+                        panes_area
+                    } else {
+                        Rect::default()
+                    }
+                })
+                .collect()
+        } else {
+            layout::tile(&self.tiling, panes_area, &names, &shown)
+        };
+        Areas {
+            main,
+            panes,
+            sidebar,
+            bar,
+            footer,
+        }
+    }
+
+    /// Runs `f` on the process called `name`.
+    pub(crate) fn with_process<T>(
+        &self,
+        name: &str,
+        f: impl FnOnce(&mut ProcessScreen) -> anyhow::Result<T>,
+    ) -> anyhow::Result<T> {
+        let mut blocks = self.blocks.write();
+        let block = blocks
+            .iter_mut()
+            .find(|block| block.name == name)
+            .ok_or_else(|| anyhow::anyhow!("no process named '{}'", name))?;
+        f(block)
+    }
+
+    /// Calls `f` on every process, in order.
+    pub(crate) fn processes<T>(&self, f: impl Fn(&ProcessScreen) -> T) -> Vec<T> {
+        self.blocks.read().iter().map(f).collect()
+    }
+
+    /// Returns the code hawk should exit with if `policy` says it's time to stop.
+    pub(crate) fn check_exit(&self, policy: &ExitPolicy) -> Option<u8> {
+        let statuses: Vec<_> = self
+            .blocks
+            .read()
+            .iter()
+            .map(|block| (block.name.clone(), block.status()))
+            .collect();
+        policy.check(&statuses)
+    }
+
+    /// The code hawk exits with when the user quits: nonzero if any process failed.
+    pub(crate) fn exit_code(&self) -> u8 {
+        let statuses = self.processes(ProcessScreen::status);
+        status::exit_code(statuses.iter())
+    }
+
     pub(crate) fn resize(&mut self, rows: u16, cols: u16) {
         self.rows = rows;
         self.cols = cols;
@@ -68,24 +858,167 @@ impl Widget for ProcessGroup {
     where
         Self: Sized,
     {
-        let [main, footer] = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([Constraint::Fill(1), Constraint::Length(1)].as_ref())
-            .areas(area);
+        let Areas {
+            main,
+            panes,
+            sidebar,
+            bar,
+            footer,
+        } = self.layout(area);
+        let mut blocks = self.blocks.write();
 
-        let [left, right] = Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints([Constraint::Ratio(1, 2), Constraint::Ratio(1, 2)].as_ref())
-            .areas(main);
+        Sidebar::new(&blocks)
+            .sparkline(self.sparklines)
+            .render(sidebar, buf);
+        if matches!(self.mode, Mode::Logs) {
+            let names = blocks.iter().map(|block| block.name.clone()).collect();
+            MergedWidget::new(&self.merged, &self.merged_view, names).render(main, buf);
+        } else {
+            let focused = blocks.focused_index();
+            let targets = Self::broadcast_targets(&blocks);
+            let panes = panes.into_iter().zip(targets);
+            for (i, (block, (pane, target))) in blocks.iter_mut().zip(panes).enumerate() {
+                if pane.is_empty() {
+                    continue;
+                }
+                if i == focused {
+                    block.mark_seen();
+                }
+                ProcessWidget::new(block)
+                    .focused(i == focused)
+                    .highlights(&self.highlights)
+                    .broadcast(self.broadcast && target)
+                    .sparkline(self.sparklines)
+                    .render(pane, buf);
+            }
+            ProcessBar::new(&blocks).render(bar, buf);
+            match (&self.mode, blocks.get(focused)) {
+                (Mode::Details, Some(focused)) => Details::new(focused).render(main, buf),
+                (Mode::LogSearch { query, search }, Some(focused)) => {
+                    LogSearch::new(&focused.name, query, search).render(main, buf);
+                }
+                _ => {}
+            }
+        }
 
-        let mut blocks = self.blocks.write();
-        ProcessWidget::new(blocks.get_mut(0).unwrap()).render(left, buf);
-        ProcessWidget::new(blocks.get_mut(1).unwrap()).render(right, buf);
+        if self.broadcast && matches!(self.mode, Mode::Normal) {
+            let count = Self::broadcast_targets(&blocks)
+                .into_iter()
+                .filter(|target| *target)
+                .count();
+            let mut banner = format!("BROADCASTING to {} of {} processes", count, blocks.len());
+            let keys = [(Action::Broadcast, "to stop"), (Action::Mark, "to mark")];
+            for (action, purpose) in keys {
+                if let Some(keys) = self.keymap.describe(action) {
+                    banner.push_str(&format!("  ({} {})", keys, purpose));
+                }
+            }
+            Paragraph::new(banner)
+                .style(
+                    Style::default()
+                        .fg(Color::White)
+                        .bg(Color::Red)
+                        .add_modifier(Modifier::BOLD),
+                )
+                .alignment(Alignment::Center)
+                .render(footer, buf);
+            return;
+        }
 
-        let explanation = "Press q to exit".to_string();
+        let explanation = match &self.mode {
+            Mode::Normal => self.keymap.hint(),
+            Mode::Prefix => format!("{} ...", self.keymap.prefix),
+            Mode::Scroll => "SCROLL  (PgUp/PgDn/Up/Down/g/G scroll, Esc exit)".to_owned(),
+            Mode::Copy => "COPY  (v select, V select lines, y yank, Esc exit)".to_owned(),
+            Mode::Filter { query } => format!(
+                "FILTER  {}  (regex, ! to hide matches, Enter to keep, Esc to clear)",
+                query
+            ),
+            Mode::Details => "DETAILS  (Esc to close)".to_owned(),
+            Mode::Logs => {
+                "ALL LOGS  (Up/Down/PgUp/PgDn/g/G scroll, 1-9 show/hide process, a show all, Esc exit)"
+                    .to_owned()
+            }
+            Mode::Search { query, editing } => {
+                let focused = blocks.focused();
+                let summary = focused.search_summary().unwrap_or_default();
+                let hint = match (*editing, focused.has_log()) {
+                    (true, _) => "Enter to finish, Esc to cancel",
+                    (false, true) => "n/N next/previous, / to edit, l search the log, Esc to exit",
+                    (false, false) => "n/N next/previous, / to edit, Esc to exit",
+                };
+                format!("/{}  {}  ({})", query, summary, hint)
+            }
+            Mode::LogSearch { query, .. } => format!("LOG SEARCH  /{}  (Esc to close)", query),
+        };
         let explanation = Paragraph::new(explanation)
             .style(Style::default().add_modifier(Modifier::BOLD | Modifier::REVERSED))
             .alignment(Alignment::Center);
         explanation.render(footer, buf);
     }
 }
+
+/// The marked processes, or all of them when none are marked.
+fn marked_or_all(marked: &[bool]) -> Vec<bool> {
+    let any_marked = marked.contains(&true);
+    marked.iter().map(|marked| *marked || !any_marked).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keymap::KeyChord;
+
+    #[test]
+    fn broadcasts_to_the_marked_processes() {
+        assert_eq!(
+            vec![true, true, true],
+            marked_or_all(&[false, false, false])
+        );
+        assert_eq!(
+            vec![false, true, false],
+            marked_or_all(&[false, true, false])
+        );
+    }
+
+    #[tokio::test]
+    async fn marks_and_unmarks_the_focused_process() {
+        let mut group = ProcessGroup::new(
+            24,
+            80,
+            100,
+            Keymap::new(KeyChord::new(KeyCode::Char('a'), KeyModifiers::CONTROL)),
+            EventBus::default(),
+            &Config::default(),
+            LayoutMode::default(),
+        );
+        for name in ["a", "b", "c"] {
+            group
+                .add(name, CommandBuilder::new("true"), Outputs::default())
+                .unwrap();
+        }
+        let marks = |group: &ProcessGroup| -> Vec<bool> {
+            group
+                .blocks
+                .read()
+                .iter()
+                .map(|block| block.marked)
+                .collect()
+        };
+
+        group.perform(Action::FocusNext).unwrap();
+        group.perform(Action::Mark).unwrap();
+        assert_eq!(vec![false, true, false], marks(&group));
+        group.perform(Action::FocusNext).unwrap();
+        group.perform(Action::Mark).unwrap();
+        assert_eq!(vec![false, true, true], marks(&group));
+        group.perform(Action::FocusPrev).unwrap();
+        group.perform(Action::Mark).unwrap();
+        assert_eq!(vec![false, false, true], marks(&group));
+        let blocks = group.blocks.read();
+        assert_eq!(
+            vec![false, false, true],
+            ProcessGroup::broadcast_targets(&blocks)
+        );
+    }
+}
@@ -0,0 +1,199 @@
+/// A line of a process's history, as text plus the screen column of each char.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Line {
+    text: String,
+    columns: Vec<u16>,
+}
+
+impl Line {
+    fn from_row(screen: &vt100::Screen, row: u16, cols: u16) -> Self {
+        let mut line = Line::default();
+        let mut blanks = Vec::new();
+        for col in 0..cols {
+            let Some(cell) = screen.cell(row, col) else {
+                break;
+            };
+            if cell.is_wide_continuation() {
+                continue;
+            }
+            if !cell.has_contents() {
+                // Blank cells only count once something follows them.
+                blanks.push(col);
+                continue;
+            }
+            for blank in blanks.drain(..) {
+                line.text.push(' ');
+                line.columns.push(blank);
+            }
+            for c in cell.contents().chars() {
+                line.text.push(c);
+                line.columns.push(col);
+            }
+        }
+        line
+    }
+
+    pub(crate) fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// The column just past the last non-blank cell.
+    pub(crate) fn width(&self) -> u16 {
+        self.columns.last().map_or(0, |col| col + 1)
+    }
+
+    /// The screen column a byte offset into `text` was rendered at.
+    pub(crate) fn column(&self, byte: usize) -> u16 {
+        let index = self.text[..byte].chars().count();
+        // A match ending at the end of the line ends just past its last column.
+        self.columns
+            .get(index)
+            .copied()
+            .unwrap_or_else(|| self.width())
+    }
+
+    /// The text rendered from column `start` up to, but not including, `end`.
+    pub(crate) fn slice(&self, start: u16, end: u16) -> String {
+        self.cells()
+            .filter(|(col, _)| (start..end).contains(col))
+            .map(|(_, c)| c)
+            .collect()
+    }
+
+    pub(crate) fn first_word(&self) -> Option<u16> {
+        self.word_starts().next()
+    }
+
+    /// The column of the first word that starts after `col`.
+    pub(crate) fn next_word(&self, col: u16) -> Option<u16> {
+        self.word_starts().find(|start| *start > col)
+    }
+
+    /// The column of the last word that starts before `col`.
+    pub(crate) fn prev_word(&self, col: u16) -> Option<u16> {
+        self.word_starts().take_while(|start| *start < col).last()
+    }
+
+    fn word_starts(&self) -> impl Iterator<Item = u16> + '_ {
+        let mut blank = true;
+        self.cells().filter_map(move |(col, c)| {
+            let start = blank && !c.is_whitespace();
+            blank = c.is_whitespace();
+            start.then_some(col)
+        })
+    }
+
+    fn cells(&self) -> impl Iterator<Item = (u16, char)> + '_ {
+        self.columns.iter().copied().zip(self.text.chars())
+    }
+}
+
+/// The number of lines in `parser`'s scrollback, above the visible screen.
+pub(crate) fn history_len(parser: &mut vt100::Parser) -> usize {
+    let offset = parser.screen().scrollback();
+    // vt100 clamps the offset to the length of the scrollback.
+    parser.set_scrollback(usize::MAX);
+    let len = parser.screen().scrollback();
+    parser.set_scrollback(offset);
+    len
+}
+
+/// How many lines have dropped off the front of `parser`'s scrollback, so that
+/// adding it to an index into [`history`] numbers lines the same way for good.
+pub(crate) fn dropped(parser: &vt100::Parser) -> usize {
+    parser.screen().scrollback_dropped()
+}
+
+/// Moves `parser`'s view `lines` further back into the scrollback, or towards
+/// the live screen when negative, stopping at either end.
+pub(crate) fn scroll(parser: &mut vt100::Parser, lines: isize) {
+    let offset = parser.screen().scrollback().saturating_add_signed(lines);
+    // vt100 clamps the offset to the length of the scrollback.
+    parser.set_scrollback(offset);
+}
+
+/// Scrolls by whole screens.
+pub(crate) fn scroll_page(parser: &mut vt100::Parser, pages: isize) {
+    let (rows, _) = parser.screen().size();
+    let rows = isize::try_from(rows).unwrap_or(isize::MAX);
+    scroll(parser, rows.saturating_mul(pages));
+}
+
+/// Every line of `parser`'s scrollback followed by the live screen, oldest first.
+pub(crate) fn history(parser: &mut vt100::Parser) -> Vec<Line> {
+    let offset = parser.screen().scrollback();
+    let len = history_len(parser);
+    let (rows, cols) = parser.screen().size();
+
+    let mut lines = Vec::with_capacity(len + usize::from(rows));
+    let mut top = 0;
+    while top < len + usize::from(rows) {
+        // Scroll so that line `top` is the first visible row, or as close as possible.
+        let scrollback = len.saturating_sub(top);
+        parser.set_scrollback(scrollback);
+        let first = len - scrollback;
+        let skip = top - first;
+        for row in skip..usize::from(rows) {
+            let row = u16::try_from(row).unwrap_or(u16::MAX);
+            lines.push(Line::from_row(parser.screen(), row, cols));
+        }
+        top = first + usize::from(rows);
+    }
+    parser.set_scrollback(offset);
+    lines
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::*;
+
+    pub(crate) fn parser_with_lines(count: usize) -> vt100::Parser {
+        let mut parser = vt100::Parser::new(4, 20, 100);
+        for i in 1..=count {
+            parser.process(format!("line {}\r\n", i).as_bytes());
+        }
+        parser
+    }
+
+    #[test]
+    fn history_includes_scrollback() {
+        let mut parser = parser_with_lines(10);
+        parser.set_scrollback(2);
+        let lines = history(&mut parser);
+
+        assert_eq!(7, history_len(&mut parser));
+        assert_eq!("line 1", lines[0].text.trim_end());
+        assert_eq!("line 10", lines[9].text.trim_end());
+        assert_eq!(2, parser.screen().scrollback());
+    }
+
+    #[test]
+    fn scrolls_within_the_scrollback() {
+        let mut parser = parser_with_lines(20);
+        let len = history_len(&mut parser);
+
+        scroll(&mut parser, 3);
+        assert_eq!(3, parser.screen().scrollback());
+        scroll(&mut parser, -5);
+        assert_eq!(0, parser.screen().scrollback());
+        scroll(&mut parser, isize::MAX);
+        assert_eq!(len, parser.screen().scrollback());
+        scroll(&mut parser, isize::MIN);
+        assert_eq!(0, parser.screen().scrollback());
+    }
+
+    #[test]
+    fn pages_further_back_than_a_screen() {
+        let mut parser = parser_with_lines(20);
+
+        scroll_page(&mut parser, 3);
+        assert_eq!(12, parser.screen().scrollback());
+        // The top row shows the line a dozen back from the live screen's top.
+        assert_eq!("line 6", parser.screen().contents_between(0, 0, 0, 20));
+        scroll_page(&mut parser, 2);
+        assert_eq!(history_len(&mut parser), parser.screen().scrollback());
+        assert_eq!("line 1", parser.screen().contents_between(0, 0, 0, 20));
+        scroll_page(&mut parser, -10);
+        assert_eq!(0, parser.screen().scrollback());
+    }
+}
@@ -0,0 +1,156 @@
+use clap::ValueEnum;
+use ratatui::layout::{Constraint, Direction, Flex, Layout, Rect};
+
+use crate::config::{LayoutConfig, Part, PartContent};
+
+/// How the panes share the screen.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub(crate) enum LayoutMode {
+    /// Every process gets a pane, tiled in a grid.
+    #[default]
+    Tiled,
+    /// The focused process gets the whole screen, under a tab bar listing them all.
+    Tabs,
+}
+
+impl LayoutMode {
+    /// The other layout, for switching between them.
+    pub(crate) const fn toggled(self) -> Self {
+        match self {
+            Self::Tiled => Self::Tabs,
+            Self::Tabs => Self::Tiled,
+        }
+    }
+}
+
+/// Gives each process that's `shown` a pane in `area`, split as `config` says,
+/// or tiled in a grid when it doesn't say. The rest get an empty one.
+pub(crate) fn tile(config: &LayoutConfig, area: Rect, names: &[&str], shown: &[bool]) -> Vec<Rect> {
+    let mut panes = vec![Rect::default(); names.len()];
+    if config.parts.is_empty() {
+        let indexes: Vec<usize> = (0..names.len()).filter(|&i| shown[i]).collect();
+        for (index, pane) in indexes
+            .into_iter()
+            .zip(grid(area, shown.iter().filter(|s| **s).count()))
+        {
+            panes[index] = pane;
+        }
+    } else {
+        let placed = |name: &str| names.iter().zip(shown).position(|(n, s)| *s && *n == name);
+        split(
+            &config.parts,
+            config.direction.into(),
+            area,
+            &placed,
+            &mut panes,
+        );
+    }
+    panes
+}
+
+/// Splits `area` between the `parts` that have a process to show.
+fn split(
+    parts: &[Part],
+    direction: Direction,
+    area: Rect,
+    placed: &dyn Fn(&str) -> Option<usize>,
+    panes: &mut [Rect],
+) {
+    let parts: Vec<&Part> = parts
+        .iter()
+        .filter(|part| shows_any(part, placed))
+        .collect();
+    // The last part takes whatever the others leave, so hiding never leaves a gap.
+    let areas = Layout::default()
+        .direction(direction)
+        .flex(Flex::Legacy)
+        .constraints(parts.iter().map(|part| part.size))
+        .split(area);
+    for (part, area) in parts.into_iter().zip(areas.iter()) {
+        match &part.content {
+            PartContent::Process(name) => {
+                if let Some(index) = placed(name) {
+                    panes[index] = *area;
+                }
+            }
+            PartContent::Split { direction, parts } => {
+                split(parts, (*direction).into(), *area, placed, panes);
+            }
+        }
+    }
+}
+
+fn shows_any(part: &Part, placed: &dyn Fn(&str) -> Option<usize>) -> bool {
+    match &part.content {
+        PartContent::Process(name) => placed(name).is_some(),
+        PartContent::Split { parts, .. } => parts.iter().any(|part| shows_any(part, placed)),
+    }
+}
+
+/// Tiles `count` panes in a grid with as many columns as rows, or one more.
+fn grid(area: Rect, count: usize) -> Vec<Rect> {
+    if count == 0 {
+        return Vec::new();
+    }
+    let columns = (1..=count).find(|c| c * c >= count).unwrap_or(count);
+    let rows = count.div_ceil(columns);
+
+    let row_areas = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(vec![Constraint::Fill(1); rows])
+        .split(area);
+
+    let mut panes = Vec::with_capacity(count);
+    for (i, row_area) in row_areas.iter().enumerate() {
+        let in_row = columns.min(count - i * columns);
+        let cells = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(vec![Constraint::Fill(1); in_row])
+            .split(*row_area);
+        panes.extend(cells.iter().copied());
+    }
+    panes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tiles_processes_as_configured() {
+        let config: LayoutConfig = toml::from_str(
+            r#"
+            direction = "horizontal"
+            parts = [
+                { size = "60%", parts = [{ process = "api" }, { process = "worker" }] },
+                { parts = [{ process = "db", size = "5" }, { process = "redis" }] },
+            ]
+            "#,
+        )
+        .unwrap();
+        let area = Rect::new(0, 0, 100, 20);
+        let names = ["api", "worker", "db", "redis", "other"];
+        let panes = tile(&config, area, &names, &[true; 5]);
+        assert_eq!(panes[0], Rect::new(0, 0, 60, 10));
+        assert_eq!(panes[1], Rect::new(0, 10, 60, 10));
+        assert_eq!(panes[2], Rect::new(60, 0, 40, 5));
+        assert_eq!(panes[3], Rect::new(60, 5, 40, 15));
+        assert!(panes[4].is_empty());
+
+        // Hiding db gives redis the right-hand side, and hiding both drops it.
+        let panes = tile(&config, area, &names, &[true, true, false, true, true]);
+        assert_eq!(panes[3], Rect::new(60, 0, 40, 20));
+        let panes = tile(&config, area, &names, &[true, true, false, false, true]);
+        assert_eq!(panes[0], Rect::new(0, 0, 100, 10));
+
+        let panes = tile(
+            &LayoutConfig::default(),
+            area,
+            &names,
+            &[true, false, true, true, true],
+        );
+        assert_eq!(panes[0], Rect::new(0, 0, 50, 10));
+        assert!(panes[1].is_empty());
+        assert_eq!(panes[4], Rect::new(50, 10, 50, 10));
+    }
+}
@@ -0,0 +1,89 @@
+use std::{path::PathBuf, sync::Arc};
+
+use parking_lot::Mutex;
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Clear, Paragraph},
+};
+use regex::Regex;
+use tokio::task;
+
+use crate::logs::{self, LogMatches};
+
+/// Most matching lines read from a log; the popup shows as many as fit.
+pub(crate) const MAX_MATCHES: usize = 1000;
+
+/// A search of a log running off the UI thread, since a log that's never
+/// rotated can be large, and what it found once it's done.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct BackgroundSearch {
+    found: Arc<Mutex<Option<Result<LogMatches, String>>>>,
+}
+
+impl BackgroundSearch {
+    /// Starts searching the log files at `paths` as [`logs::search`] does.
+    pub(crate) fn start(paths: Vec<PathBuf>, regex: Regex, limit: usize) -> Self {
+        let search = Self::default();
+        let found = search.found.clone();
+        task::spawn_blocking(move || {
+            let result = logs::search(&paths, &regex, limit).map_err(|e| e.to_string());
+            *found.lock() = Some(result);
+        });
+        search
+    }
+}
+
+/// A popup with the lines of a process's log that match the search, newest at
+/// the bottom, for output that's left the scrollback.
+pub(crate) struct LogSearch<'a> {
+    name: &'a str,
+    query: &'a str,
+    search: &'a BackgroundSearch,
+}
+
+impl<'a> LogSearch<'a> {
+    pub(crate) fn new(name: &'a str, query: &'a str, search: &'a BackgroundSearch) -> Self {
+        Self {
+            name,
+            query,
+            search,
+        }
+    }
+}
+
+impl<'a> Widget for LogSearch<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer)
+    where
+        Self: Sized,
+    {
+        let popup = area.inner(Margin::new(2, 1));
+        let rows = usize::from(popup.height.saturating_sub(2));
+        let found = self.search.found.lock();
+        let (lines, summary) = match &*found {
+            None => (vec![Line::from("Searching the log…")], None),
+            Some(Ok(found)) if found.total == 0 => {
+                (vec![Line::from("No matches in the log")], None)
+            }
+            Some(Ok(found)) => {
+                let shown = &found.lines[found.lines.len().saturating_sub(rows)..];
+                let lines = shown.iter().map(|line| Line::from(line.as_str())).collect();
+                let summary = format!(" last {} of {} matches ", shown.len(), found.total);
+                (lines, Some(summary))
+            }
+            Some(Err(error)) => (
+                vec![Line::from(format!("Couldn't read the log: {}", error))],
+                None,
+            ),
+        };
+
+        let mut block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan))
+            .title(format!(" {}.log: /{}/ ", self.name, self.query));
+        if let Some(summary) = summary {
+            block = block.title_bottom(summary);
+        }
+        Clear.render(popup, buf);
+        Paragraph::new(lines).block(block).render(popup, buf);
+    }
+}
@@ -0,0 +1,110 @@
+use regex::Regex;
+
+use crate::events::{Event, EventBus};
+
+/// Patterns looked for in a process's output, one line at a time.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Patterns {
+    /// Output that means the process is ready.
+    pub(crate) ready: Option<Regex>,
+    /// Output that means the process is no longer ready, until it next prints
+    /// its ready pattern.
+    pub(crate) unhealthy: Option<Regex>,
+    /// Output worth an event whenever it appears.
+    pub(crate) watch: Vec<Regex>,
+}
+
+/// Publishes events for the lines of one run's output that match its patterns.
+#[derive(Debug)]
+pub(crate) struct LineMatcher {
+    name: String,
+    patterns: Patterns,
+    events: EventBus,
+    ready: bool,
+    unhealthy: bool,
+}
+
+impl LineMatcher {
+    pub(crate) fn new(name: &str, patterns: &Patterns, events: &EventBus) -> Self {
+        Self {
+            name: name.to_owned(),
+            patterns: patterns.clone(),
+            events: events.clone(),
+            ready: false,
+            unhealthy: false,
+        }
+    }
+
+    /// Whether the run has printed its ready pattern.
+    pub(crate) fn is_ready(&self) -> bool {
+        self.ready
+    }
+
+    /// Whether the run has printed its unhealthy pattern since it was last ready.
+    pub(crate) fn is_unhealthy(&self) -> bool {
+        self.unhealthy
+    }
+
+    pub(crate) fn match_line(&mut self, line: &str) {
+        let matches = |pattern: &Option<Regex>| pattern.as_ref().is_some_and(|r| r.is_match(line));
+        if !self.ready && matches(&self.patterns.ready) {
+            self.ready = true;
+            self.unhealthy = false;
+            self.events.publish(&Event::Ready {
+                process: self.name.clone(),
+            });
+        } else if !self.unhealthy && matches(&self.patterns.unhealthy) {
+            self.ready = false;
+            self.unhealthy = true;
+            self.events.publish(&Event::Unhealthy {
+                process: self.name.clone(),
+                line: line.to_owned(),
+            });
+        }
+        for pattern in self.patterns.watch.iter().filter(|r| r.is_match(line)) {
+            self.events.publish(&Event::OutputMatched {
+                process: self.name.clone(),
+                pattern: pattern.as_str().to_owned(),
+                line: line.to_owned(),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn goes_unhealthy_until_ready_again() {
+        let patterns = Patterns {
+            ready: Some(Regex::new("listening").unwrap()),
+            unhealthy: Some(Regex::new("lost database").unwrap()),
+            watch: Vec::new(),
+        };
+        let events = EventBus::default();
+        let mut published = events.subscribe();
+        let mut matcher = LineMatcher::new("web", &patterns, &events);
+        let mut states = Vec::new();
+        for line in [
+            "listening on 3000",
+            "lost database",
+            "lost database",
+            "listening on 3000",
+        ] {
+            matcher.match_line(line);
+            states.push((matcher.is_ready(), matcher.is_unhealthy()));
+        }
+        assert_eq!(
+            vec![(true, false), (false, true), (false, true), (true, false)],
+            states
+        );
+
+        let mut kinds = Vec::new();
+        while let Ok(event) = published.try_recv() {
+            let event: serde_json::Value = serde_json::from_str(&event).unwrap();
+            kinds.push(event["event"].as_str().unwrap().to_owned());
+        }
+        assert_eq!(vec!["ready", "unhealthy", "ready"], kinds);
+    }
+}
@@ -0,0 +1,231 @@
+use std::{collections::VecDeque, sync::Arc, time::SystemTime};
+
+use parking_lot::RwLock;
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Paragraph},
+};
+
+use crate::logs;
+
+/// Colours process names cycle through, so each is easy to pick out.
+const COLORS: [Color; 6] = [
+    Color::Cyan,
+    Color::Green,
+    Color::Yellow,
+    Color::Magenta,
+    Color::LightBlue,
+    Color::LightRed,
+];
+
+/// The colour of the `index`th process's name.
+pub(crate) fn color(index: usize) -> Color {
+    COLORS[index % COLORS.len()]
+}
+
+#[derive(Debug)]
+struct MergedLine {
+    time: SystemTime,
+    process: usize,
+    text: String,
+}
+
+/// Every process's output, a line at a time, in the order it arrived.
+#[derive(Debug, Clone)]
+pub(crate) struct MergedLog {
+    lines: Arc<RwLock<VecDeque<MergedLine>>>,
+    capacity: usize,
+}
+
+impl MergedLog {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            lines: Arc::default(),
+            capacity,
+        }
+    }
+
+    /// Where the `process`th process's lines go.
+    pub(crate) fn source(&self, process: usize) -> MergedSource {
+        MergedSource {
+            log: self.clone(),
+            process,
+        }
+    }
+
+    /// How many lines `view` shows, all told.
+    fn len(&self, view: &MergedView) -> usize {
+        let lines = self.lines.read();
+        lines
+            .iter()
+            .filter(|line| !view.is_hidden(line.process))
+            .count()
+    }
+}
+
+/// One process's way into the merged log.
+#[derive(Debug, Clone)]
+pub(crate) struct MergedSource {
+    log: MergedLog,
+    process: usize,
+}
+
+impl MergedSource {
+    pub(crate) fn push(&self, text: &str) {
+        let mut lines = self.log.lines.write();
+        if lines.len() >= self.log.capacity {
+            lines.pop_front();
+        }
+        lines.push_back(MergedLine {
+            time: SystemTime::now(),
+            process: self.process,
+            text: text.to_owned(),
+        });
+    }
+}
+
+/// How far back the merged view is scrolled and which processes it hides.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct MergedView {
+    /// Lines back from the newest.
+    scroll: usize,
+    hidden: Vec<bool>,
+}
+
+impl MergedView {
+    fn is_hidden(&self, process: usize) -> bool {
+        self.hidden.get(process).copied().unwrap_or(false)
+    }
+
+    /// Shows or hides the `process`th process's lines.
+    pub(crate) fn toggle(&mut self, process: usize) {
+        if self.hidden.len() <= process {
+            self.hidden.resize(process + 1, false);
+        }
+        self.hidden[process] = !self.hidden[process];
+        self.scroll = 0;
+    }
+
+    pub(crate) fn show_all(&mut self) {
+        self.hidden.clear();
+        self.scroll = 0;
+    }
+
+    /// Scrolls `lines` further back, or towards the newest when negative.
+    pub(crate) fn scroll(&mut self, lines: isize, log: &MergedLog) {
+        let oldest = log.len(self).saturating_sub(1);
+        self.scroll = self.scroll.saturating_add_signed(lines).min(oldest);
+    }
+
+    pub(crate) fn scroll_to_top(&mut self, log: &MergedLog) {
+        self.scroll = log.len(self).saturating_sub(1);
+    }
+
+    pub(crate) fn scroll_to_bottom(&mut self) {
+        self.scroll = 0;
+    }
+}
+
+/// Renders the merged log as `view` shows it, with each line's time and process.
+pub(crate) struct MergedWidget<'a> {
+    log: &'a MergedLog,
+    view: &'a MergedView,
+    names: Vec<String>,
+}
+
+impl<'a> MergedWidget<'a> {
+    pub(crate) fn new(log: &'a MergedLog, view: &'a MergedView, names: Vec<String>) -> Self {
+        Self { log, view, names }
+    }
+
+    /// The title, listing the processes by the number that shows or hides them.
+    fn title(&self) -> Line<'static> {
+        let mut spans = vec![Span::raw(" all logs ")];
+        for (i, name) in self.names.iter().enumerate() {
+            let style = if self.view.is_hidden(i) {
+                Style::default()
+                    .fg(Color::DarkGray)
+                    .add_modifier(Modifier::CROSSED_OUT)
+            } else {
+                Style::default().fg(color(i))
+            };
+            spans.push(Span::styled(format!(" {} {} ", i + 1, name), style));
+        }
+        if self.view.scroll > 0 {
+            spans.push(Span::raw(format!(
+                " [scrolled {} lines] ",
+                self.view.scroll
+            )));
+        }
+        Line::from(spans)
+    }
+}
+
+impl<'a> Widget for MergedWidget<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer)
+    where
+        Self: Sized,
+    {
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan))
+            .title(self.title());
+        let height = usize::from(block.inner(area).height);
+        let width = self.names.iter().map(String::len).max().unwrap_or(0);
+
+        let lines = self.log.lines.read();
+        let mut shown: Vec<Line> = lines
+            .iter()
+            .rev()
+            .filter(|line| !self.view.is_hidden(line.process))
+            .skip(self.view.scroll)
+            .take(height)
+            .map(|line| {
+                let name = self.names.get(line.process).map_or("", String::as_str);
+                Line::from(vec![
+                    Span::styled(
+                        format!("{} ", logs::clock(line.time)),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                    Span::styled(
+                        format!("{:width$} ", name),
+                        Style::default()
+                            .fg(color(line.process))
+                            .add_modifier(Modifier::BOLD),
+                    ),
+                    Span::raw(line.text.clone()),
+                ])
+            })
+            .collect();
+        drop(lines);
+        shown.reverse();
+
+        Paragraph::new(shown).block(block).render(area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_the_newest_lines_and_hides_processes() {
+        let log = MergedLog::new(3);
+        let (web, worker) = (log.source(0), log.source(1));
+        web.push("one");
+        worker.push("two");
+        web.push("three");
+        worker.push("four");
+
+        let mut view = MergedView::default();
+        assert_eq!(log.len(&view), 3);
+        view.scroll(10, &log);
+        assert_eq!(view.scroll, 2);
+
+        view.toggle(1);
+        assert_eq!(view.scroll, 0);
+        assert_eq!(log.len(&view), 1);
+        view.show_all();
+        assert_eq!(log.len(&view), 3);
+    }
+}
@@ -0,0 +1,180 @@
+use std::sync::{
+    atomic::{AtomicBool, AtomicUsize, Ordering},
+    Arc,
+};
+
+use parking_lot::Mutex;
+
+use super::{
+    filter::TextLines,
+    matcher::{LineMatcher, Patterns},
+    merged::MergedSource,
+};
+use crate::{ansi, cast::Recorder, config::HighlightRule, events::EventBus, logs::ProcessLog};
+
+/// Longest line kept for matching, filtering and the merged log; anything further along is dropped.
+const MAX_LINE: usize = 64 * 1024;
+
+/// Where a process's output goes besides its screen.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Outputs {
+    pub(crate) patterns: Patterns,
+    /// The process's log under `--log-dir`, kept open across restarts.
+    pub(crate) log: Option<Arc<Mutex<ProcessLog>>>,
+    /// The process's recording under `--record`, kept open across restarts.
+    pub(crate) record: Option<Arc<Mutex<Recorder>>>,
+    /// Where the process's lines go in the merged view, which the group fills in.
+    pub(crate) merged: Option<MergedSource>,
+    /// The rules whose error matches are counted, which the group fills in too.
+    pub(crate) highlights: Arc<[HighlightRule]>,
+}
+
+/// What a process has done since it was last focused, and whether the
+/// current run is ready.
+#[derive(Debug, Default)]
+pub(crate) struct Activity {
+    /// Lines matching an error highlight rule.
+    errors: AtomicUsize,
+    /// Whether there's been any output.
+    unread: AtomicBool,
+    /// Whether the current run has printed its ready pattern.
+    ready: AtomicBool,
+    /// Whether the current run has printed its unhealthy pattern since it was ready.
+    unhealthy: AtomicBool,
+}
+
+impl Activity {
+    pub(crate) fn errors(&self) -> usize {
+        self.errors.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn unread(&self) -> bool {
+        self.unread.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn ready(&self) -> bool {
+        self.ready.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn unhealthy(&self) -> bool {
+        self.unhealthy.load(Ordering::Relaxed)
+    }
+
+    /// Forgets the previous run's readiness.
+    pub(crate) fn new_run(&self) {
+        self.ready.store(false, Ordering::Relaxed);
+        self.unhealthy.store(false, Ordering::Relaxed);
+    }
+
+    pub(crate) fn seen(&self) {
+        self.errors.store(0, Ordering::Relaxed);
+        self.unread.store(false, Ordering::Relaxed);
+    }
+}
+
+/// Hands one run's output to everything in its [`Outputs`].
+#[derive(Debug)]
+pub(crate) struct OutputSink {
+    matcher: LineMatcher,
+    log: Option<Arc<Mutex<ProcessLog>>>,
+    record: Option<Arc<Mutex<Recorder>>>,
+    merged: Option<MergedSource>,
+    lines: TextLines,
+    highlights: Arc<[HighlightRule]>,
+    activity: Arc<Activity>,
+    line: Vec<u8>,
+}
+
+impl OutputSink {
+    pub(crate) fn new(
+        name: &str,
+        outputs: &Outputs,
+        events: &EventBus,
+        lines: TextLines,
+        activity: Arc<Activity>,
+    ) -> Self {
+        Self {
+            matcher: LineMatcher::new(name, &outputs.patterns, events),
+            log: outputs.log.clone(),
+            record: outputs.record.clone(),
+            merged: outputs.merged.clone(),
+            lines,
+            highlights: outputs.highlights.clone(),
+            activity,
+            line: Vec::new(),
+        }
+    }
+
+    pub(crate) fn write(&mut self, output: &[u8]) {
+        self.activity.unread.store(true, Ordering::Relaxed);
+        if let Some(log) = &self.log {
+            log.lock().write(output);
+        }
+        if let Some(record) = &self.record {
+            record.lock().output(output);
+        }
+        for chunk in output.split_inclusive(|byte| *byte == b'\n') {
+            let room = MAX_LINE.saturating_sub(self.line.len());
+            self.line.extend(chunk.iter().take(room));
+            if chunk.ends_with(b"\n") {
+                self.finish_line();
+                self.line.clear();
+            }
+        }
+    }
+
+    fn finish_line(&mut self) {
+        let line = ansi::strip(&self.line);
+        let line = line.trim_end_matches('\n');
+        self.matcher.match_line(line);
+        self.activity
+            .ready
+            .store(self.matcher.is_ready(), Ordering::Relaxed);
+        self.activity
+            .unhealthy
+            .store(self.matcher.is_unhealthy(), Ordering::Relaxed);
+        if let Some(merged) = &self.merged {
+            merged.push(line);
+        }
+        self.lines.push(line);
+        let error = |rule: &HighlightRule| rule.error && rule.pattern.is_match(line);
+        if self.highlights.iter().any(error) {
+            self.activity.errors.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_activity_until_seen() {
+        let outputs = Outputs {
+            highlights: HighlightRule::defaults().into(),
+            ..Outputs::default()
+        };
+        let activity = Arc::new(Activity::default());
+        let mut sink = OutputSink::new(
+            "web",
+            &outputs,
+            &EventBus::default(),
+            TextLines::new(10),
+            activity.clone(),
+        );
+        assert!(!activity.unread());
+
+        sink.write(b"listening\r\nwarning: slow\r\n");
+        assert!(activity.unread());
+        assert_eq!(0, activity.errors());
+
+        sink.write(b"\x1b[31mERROR\x1b[0m: lost database\r\npanic");
+        assert_eq!(1, activity.errors());
+        sink.write(b"ked\n");
+        assert_eq!(2, activity.errors());
+
+        activity.seen();
+        assert!(!activity.unread());
+        assert_eq!(0, activity.errors());
+    }
+}
@@ -0,0 +1,89 @@
+use std::{
+    collections::VecDeque,
+    fmt,
+    time::{Duration, SystemTime},
+};
+
+/// Previous runs remembered per process.
+const KEPT_RUNS: usize = 20;
+
+/// What started a run of a process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum StartedBy {
+    /// Started along with hawk.
+    Launch,
+    /// Restarted from the keyboard.
+    Key,
+    /// Started or restarted through the control socket.
+    Ctl,
+}
+
+impl fmt::Display for StartedBy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StartedBy::Launch => f.write_str("launch"),
+            StartedBy::Key => f.write_str("key"),
+            StartedBy::Ctl => f.write_str("ctl"),
+        }
+    }
+}
+
+/// A run of a process that another has since replaced.
+#[derive(Debug, Clone)]
+pub(crate) struct Run {
+    pub(crate) started: SystemTime,
+    pub(crate) started_by: StartedBy,
+    /// How long it ran, up to its exit or to being replaced if it was still running.
+    pub(crate) duration: Duration,
+    /// How it ended, like "Exited with code 1", or "killed" if it was still running.
+    pub(crate) ended: String,
+}
+
+/// The most recent previous runs of a process, oldest first.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct RunHistory {
+    runs: VecDeque<Run>,
+}
+
+impl RunHistory {
+    pub(crate) fn push(&mut self, run: Run) {
+        if self.runs.len() == KEPT_RUNS {
+            self.runs.pop_front();
+        }
+        self.runs.push_back(run);
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.runs.is_empty()
+    }
+
+    pub(crate) fn iter(&self) -> impl DoubleEndedIterator<Item = &Run> {
+        self.runs.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(ended: &str) -> Run {
+        Run {
+            started: SystemTime::UNIX_EPOCH,
+            started_by: StartedBy::Key,
+            duration: Duration::from_secs(1),
+            ended: ended.to_owned(),
+        }
+    }
+
+    #[test]
+    fn keeps_the_latest_runs() {
+        let mut history = RunHistory::default();
+        for i in 0..KEPT_RUNS + 2 {
+            history.push(run(&i.to_string()));
+        }
+        let ended: Vec<_> = history.iter().map(|run| run.ended.as_str()).collect();
+        assert_eq!(KEPT_RUNS, ended.len());
+        assert_eq!(Some(&"2"), ended.first());
+        assert_eq!(Some(&(KEPT_RUNS + 1).to_string().as_str()), ended.last());
+    }
+}
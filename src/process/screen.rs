@@ -1,23 +1,105 @@
 use std::{
-    io::{BufWriter, Read, Write},
+    io::{self, BufWriter, Read, Write},
     sync::Arc,
+    time::{Duration, Instant, SystemTime},
 };
 
 use bytes::Bytes;
-use parking_lot::RwLock;
-use portable_pty::{CommandBuilder, MasterPty, NativePtySystem, PtySize, PtySystem, SlavePty};
+use crossterm::event::{KeyEvent, MouseEvent};
+use nix::{
+    errno::Errno,
+    sys::{
+        signal::{killpg, Signal},
+        wait::{waitpid, WaitStatus},
+    },
+    unistd::Pid,
+};
+use parking_lot::{Mutex, RwLock};
+use portable_pty::{
+    Child, CommandBuilder, ExitStatus, MasterPty, NativePtySystem, PtySize, PtySystem, SlavePty,
+};
 use tokio::{
-    sync::mpsc::{channel, Receiver, Sender},
-    task,
+    sync::{
+        broadcast,
+        mpsc::{channel, Receiver, Sender},
+    },
+    task, time,
+};
+
+use super::{
+    copy::{CopyMode, Motion, Point},
+    filter::{Filter, TextLines},
+    history::{self, history, history_len},
+    logsearch::BackgroundSearch,
+    output::{Activity, OutputSink, Outputs},
+    runs::{Run, RunHistory, StartedBy},
+    search::{Match, Search},
+    status::{Limit, ProcessStatus},
+    usage::{Usage, UsageHistory},
+};
+use crate::{
+    events::{Event, EventBus},
+    input::{encode_key, encode_mouse},
 };
 
+/// Chunks of output an attached client can fall behind by before it misses some.
+const OUTPUT_BACKLOG: usize = 1024;
+
+/// How long a process gets to exit after being hung up on before it's killed.
+pub(crate) const KILL_GRACE: Duration = Duration::from_secs(5);
+
+const PASTE_START: &str = "\x1b[200~";
+const PASTE_END: &str = "\x1b[201~";
+
+/// Why hawk is ending a run, which decides how it's reported once it exits.
+#[derive(Debug, Clone, Copy)]
+enum Ending {
+    /// To run it again, so its exit is reported like any other.
+    Restart,
+    /// To keep it down until it's started again.
+    Stop,
+    /// For going over one of its limits.
+    OverLimit(Limit),
+}
+
 #[derive(Clone)]
 pub(crate) struct ProcessScreen {
     pub(crate) name: String,
+    cmd: CommandBuilder,
     sender: Option<Sender<Bytes>>,
     tasks: Option<Arc<Vec<task::JoinHandle<()>>>>,
+    pty: Option<Arc<Mutex<Box<dyn MasterPty + Send>>>>,
+    pid: Option<u32>,
+    /// Raw output from the child, for anyone attached to it.
+    output: broadcast::Sender<Bytes>,
+    events: EventBus,
+    outputs: Outputs,
     pub(crate) sized: bool,
+    /// Whether broadcast input goes to this process.
+    pub(crate) marked: bool,
     pub(crate) parser: Arc<RwLock<vt100::Parser>>,
+    pub(crate) status: Arc<RwLock<ProcessStatus>>,
+    pub(crate) search: Arc<RwLock<Option<Search>>>,
+    pub(crate) copy: Arc<RwLock<Option<CopyMode>>>,
+    /// The output as lines of text, which a filter picks from.
+    pub(crate) lines: TextLines,
+    pub(crate) filter: Arc<RwLock<Option<Filter>>>,
+    activity: Arc<Activity>,
+    /// CPU and memory samples of the current run.
+    pub(crate) usage: UsageHistory,
+    /// When the current run started.
+    started: Option<Instant>,
+    started_at: SystemTime,
+    started_by: StartedBy,
+    /// When the current run exited, once it has.
+    exited: Arc<RwLock<Option<Instant>>>,
+    /// Why hawk is ending the current run, once it's started to.
+    ending: Arc<RwLock<Option<Ending>>>,
+    /// Whether the current run's output is still wanted. A reader holds this
+    /// while it writes, so once it's cleared nothing more gets through.
+    live: Arc<RwLock<bool>>,
+    restarts: usize,
+    runs: RunHistory,
 }
 
 impl ProcessScreen {
@@ -26,10 +108,50 @@ impl ProcessScreen {
         cmd: CommandBuilder,
         rows: u16,
         cols: u16,
+        scrollback: usize,
+        events: EventBus,
+        outputs: Outputs,
     ) -> anyhow::Result<Self> {
-        let pty_system = NativePtySystem::default();
+        let mut screen = Self {
+            name,
+            cmd,
+            sender: None,
+            tasks: None,
+            pty: None,
+            pid: None,
+            output: broadcast::channel(OUTPUT_BACKLOG).0,
+            events,
+            outputs,
+            parser: Arc::new(RwLock::new(vt100::Parser::new(rows, cols, scrollback))),
+            status: Arc::new(RwLock::new(ProcessStatus::default())),
+            search: Arc::new(RwLock::new(None)),
+            copy: Arc::new(RwLock::new(None)),
+            lines: TextLines::new(scrollback.max(usize::from(rows))),
+            filter: Arc::new(RwLock::new(None)),
+            activity: Arc::default(),
+            usage: UsageHistory::default(),
+            started: None,
+            started_at: SystemTime::now(),
+            started_by: StartedBy::Launch,
+            exited: Arc::default(),
+            ending: Arc::default(),
+            live: Arc::default(),
+            restarts: 0,
+            runs: RunHistory::default(),
+            sized: false,
+            marked: false,
+        };
+        screen.spawn()?;
+        Ok(screen)
+    }
 
-        let parser = Arc::new(RwLock::new(vt100::Parser::new(rows, cols, 0)));
+    /// Runs the command in a fresh pty the size of the screen.
+    fn spawn(&mut self) -> anyhow::Result<()> {
+        let pty_system = NativePtySystem::default();
+        let (rows, cols) = self.parser.read().screen().size();
+        if let Some(record) = &self.outputs.record {
+            record.lock().start(rows, cols);
+        }
 
         let pair = pty_system.openpty(PtySize {
             rows,
@@ -38,49 +160,567 @@ impl ProcessScreen {
             pixel_height: 0,
         })?;
 
-        let child_task = task::spawn(async move {
-            Self::command_runner(cmd, pair.slave);
+        let child = pair.slave.spawn_command(self.cmd.clone())?;
+        self.pid = child.process_id();
+        self.started = Some(Instant::now());
+        self.started_at = SystemTime::now();
+        self.activity.new_run();
+        self.usage.clear();
+        self.events.publish(&Event::Started {
+            process: self.name.clone(),
+            pid: self.pid,
+        });
+
+        // Each run gets its own status, so a previous run exiting can't overwrite it.
+        let status = Arc::new(RwLock::new(ProcessStatus::default()));
+        let exited = Arc::new(RwLock::new(None));
+        let ending = Arc::new(RwLock::new(None));
+        let live = Arc::new(RwLock::new(true));
+        let child_task = task::spawn_blocking({
+            let status = status.clone();
+            let exited = exited.clone();
+            let ending = ending.clone();
+            let name = self.name.clone();
+            let events = self.events.clone();
+            move || {
+                Self::command_runner(child, pair.slave, &status, &exited, &ending, &name, &events);
+            }
         });
 
         let reader = pair.master.try_clone_reader()?;
 
-        let output_task = task::spawn(Self::output_reader(reader, parser.clone()));
+        let output_task = task::spawn_blocking({
+            let parser = self.parser.clone();
+            let output = self.output.clone();
+            let live = live.clone();
+            let mut sink = OutputSink::new(
+                &self.name,
+                &self.outputs,
+                &self.events,
+                self.lines.clone(),
+                self.activity.clone(),
+            );
+            move || Self::output_reader(reader, &live, &parser, &output, &mut sink)
+        });
 
         let (tx, rx) = channel::<Bytes>(32);
 
-        let writer = BufWriter::new(pair.master.take_writer().unwrap());
+        let writer = BufWriter::new(pair.master.take_writer()?);
 
-        let writer_task = tokio::spawn(Self::output_writer(rx, writer, pair.master));
+        let writer_task = tokio::spawn(Self::output_writer(rx, writer));
 
-        Ok(Self {
-            name,
-            sender: Some(tx),
-            parser,
-            tasks: Some(Arc::new(vec![child_task, writer_task, output_task])),
-            sized: false,
-        })
+        self.sender = Some(tx);
+        self.status = status;
+        self.exited = exited;
+        self.ending = ending;
+        self.live = live;
+        self.tasks = Some(Arc::new(vec![child_task, writer_task, output_task]));
+        self.pty = Some(Arc::new(Mutex::new(pair.master)));
+        Ok(())
+    }
+
+    /// Starts ending the current run so that [`Self::restart`] can run the
+    /// process again once it has exited. Its exit is reported like any other.
+    pub(crate) fn end_run(&self) -> io::Result<()> {
+        self.terminate(Ending::Restart)
+    }
+
+    /// Starts the process again after its last run has exited, keeping its history.
+    pub(crate) fn restart(&mut self, by: StartedBy) -> anyhow::Result<()> {
+        if self.status().is_running() {
+            anyhow::bail!("{} is still running after being killed", self.name);
+        }
+        self.events.publish(&Event::Restarted {
+            process: self.name.clone(),
+        });
+        self.restarts += 1;
+        self.respawn("restarted", by)
+    }
+
+    /// Runs the command again, remembering the last run and marking where the
+    /// new run's output starts.
+    fn respawn(&mut self, marker: &str, by: StartedBy) -> anyhow::Result<()> {
+        // Anything the last run started may still hold its pty, but whatever
+        // it prints from now on mustn't be taken for the new run's output.
+        *self.live.write() = false;
+        self.stop_tasks();
+        let status = self.status();
+        if let Some(started) = self.started {
+            let ended = match status {
+                ProcessStatus::Running => "killed".to_owned(),
+                ProcessStatus::Exited(status, _) => status.to_string(),
+                ProcessStatus::OverLimit(limit, _) => limit.to_string(),
+                ProcessStatus::Stopped => "stopped".to_owned(),
+            };
+            let exited = self.exited.read().unwrap_or_else(Instant::now);
+            self.runs.push(Run {
+                started: self.started_at,
+                started_by: self.started_by,
+                duration: exited - started,
+                ended,
+            });
+        }
+        self.started_by = by;
+        self.parser
+            .write()
+            .process(format!("\x1b[0m\r\n\x1b[2m[{}]\x1b[0m\r\n", marker).as_bytes());
+        self.spawn()
+    }
+
+    /// Hangs up on the process and everything it started, as closing its
+    /// terminal would, then kills whatever is left after [`KILL_GRACE`]. The
+    /// exit is reported as `ending` says once the process has exited.
+    fn terminate(&self, ending: Ending) -> io::Result<()> {
+        // The child leads its own session, so its process group has its pid.
+        let Some(group) = self
+            .pid()
+            .and_then(|pid| i32::try_from(pid).ok())
+            .map(Pid::from_raw)
+        else {
+            return Ok(());
+        };
+        {
+            let mut current = self.ending.write();
+            if current.is_some() {
+                return Ok(());
+            }
+            *current = Some(ending);
+        }
+        if let Err(e) = killpg(group, Signal::SIGHUP) {
+            self.ending.write().take();
+            return Err(e.into());
+        }
+        tokio::spawn(async move {
+            time::sleep(KILL_GRACE).await;
+            // A group's id isn't reused while anything in it is left, and
+            // once nothing is this fails harmlessly.
+            let _ = killpg(group, Signal::SIGKILL);
+        });
+        Ok(())
+    }
+
+    /// Ends the process for going over `limit`, which is reported as how it
+    /// exited once it has.
+    pub(crate) fn kill_over_limit(&self, limit: Limit) -> io::Result<()> {
+        self.terminate(Ending::OverLimit(limit))
+    }
+
+    /// Ends the process and keeps it down until it's started again. It's
+    /// reported as stopped once it has exited.
+    pub(crate) fn stop(&self) -> anyhow::Result<()> {
+        if !self.status().is_running() {
+            anyhow::bail!("{} isn't running", self.name);
+        }
+        self.terminate(Ending::Stop)?;
+        Ok(())
+    }
+
+    /// Starts the process again through the control socket after it has
+    /// exited or been stopped, which isn't counted as a restart.
+    pub(crate) fn start(&mut self) -> anyhow::Result<()> {
+        if self.status().is_running() {
+            anyhow::bail!("{} is already running", self.name);
+        }
+        self.respawn("started", StartedBy::Ctl)
+    }
+
+    /// How long the current run has been going, while it's running.
+    pub(crate) fn uptime(&self) -> Option<Duration> {
+        self.started
+            .filter(|_| self.status().is_running())
+            .map(|started| started.elapsed())
+    }
+
+    /// When the current run started.
+    pub(crate) fn started_at(&self) -> SystemTime {
+        self.started_at
+    }
+
+    /// What started the current run.
+    pub(crate) fn started_by(&self) -> StartedBy {
+        self.started_by
+    }
+
+    /// The runs before the current one, oldest first.
+    pub(crate) fn runs(&self) -> &RunHistory {
+        &self.runs
+    }
+
+    /// How many times the process has been run again after the first.
+    pub(crate) fn restarts(&self) -> usize {
+        self.restarts
+    }
+
+    /// Whether the current run has printed its `--ready` pattern, if it has one.
+    pub(crate) fn readiness(&self) -> Option<bool> {
+        self.outputs
+            .patterns
+            .ready
+            .as_ref()
+            .map(|_| self.activity.ready())
+    }
+
+    /// Whether the current run has printed its `--unhealthy` pattern since it
+    /// was last ready.
+    pub(crate) fn unhealthy(&self) -> bool {
+        self.activity.unhealthy()
+    }
+
+    /// The latest CPU and memory use of the child and its descendants, while
+    /// it's running.
+    pub(crate) fn usage(&self) -> Option<Usage> {
+        self.usage.latest().filter(|_| self.status().is_running())
+    }
+
+    /// The child's process id while it's running.
+    pub(crate) fn pid(&self) -> Option<u32> {
+        self.pid.filter(|_| self.status().is_running())
+    }
+
+    fn stop_tasks(&mut self) {
+        // Clones share the same tasks, so only the last one standing stops them.
+        if let Some(tasks) = self.tasks.take().and_then(Arc::into_inner) {
+            *self.live.write() = false;
+            for task in tasks {
+                task.abort();
+            }
+        }
+        self.sender.take();
     }
 
     pub(crate) fn recalculate_size(&mut self) {
         self.sized = false;
     }
 
+    /// Resizes the screen and the child's pty to `rows` by `cols`.
+    pub(crate) fn resize(&self, rows: u16, cols: u16) {
+        let mut parser = self.parser.write();
+        // vt100 drops the rows below the new height when shrinking, so scroll
+        // the ones the cursor has passed into the history first.
+        let (cursor_row, _) = parser.screen().cursor_position();
+        if rows > 0 && cursor_row >= rows {
+            let lines = cursor_row - rows + 1;
+            parser.process(format!("\x1b[{}S\x1b[{}A", lines, lines).as_bytes());
+        }
+        if parser.screen().size() != (rows, cols) {
+            if let Some(record) = &self.outputs.record {
+                record.lock().resize(rows, cols);
+            }
+        }
+        parser.set_size(rows, cols);
+        if let Some(pty) = &self.pty {
+            // This only fails once the child has gone, when the size no longer matters.
+            let _ = pty.lock().resize(PtySize {
+                rows,
+                cols,
+                pixel_width: 0,
+                pixel_height: 0,
+            });
+        }
+    }
+
+    pub(crate) fn status(&self) -> ProcessStatus {
+        self.status.read().clone()
+    }
+
+    /// Moves the view `lines` further back into the scrollback, or towards the
+    /// live screen when negative.
+    pub(crate) fn scroll(&self, lines: isize) {
+        history::scroll(&mut self.parser.write(), lines);
+    }
+
+    pub(crate) fn scroll_page(&self, pages: isize) {
+        history::scroll_page(&mut self.parser.write(), pages);
+    }
+
+    pub(crate) fn scroll_to_top(&self) {
+        // vt100 clamps the offset to the length of the scrollback.
+        self.parser.write().set_scrollback(usize::MAX);
+    }
+
+    pub(crate) fn scroll_to_bottom(&self) {
+        self.parser.write().set_scrollback(0);
+    }
+
+    /// Starts or updates a search of this process's history for `query`. The
+    /// history is read when the search starts and at each step, not per key.
+    pub(crate) fn search(&self, query: &str) {
+        let mut parser = self.parser.write();
+        let mut search = self.search.write();
+        let search = search.get_or_insert_with(|| {
+            let lines = history(&mut parser);
+            Search::new(lines, history::dropped(&parser))
+        });
+        search.set_query(query);
+        search.refresh();
+        Self::reveal(&mut parser, search.current_match());
+    }
+
+    /// Moves to the next (or previous) match of the current search.
+    pub(crate) fn search_step(&self, forward: bool) {
+        let mut parser = self.parser.write();
+        let mut search = self.search.write();
+        let Some(search) = search.as_mut() else {
+            return;
+        };
+        search.load(history(&mut parser), history::dropped(&parser));
+        search.refresh();
+        search.step(forward);
+        Self::reveal(&mut parser, search.current_match());
+    }
+
+    /// Whether the process's output is logged under `--log-dir`.
+    pub(crate) fn has_log(&self) -> bool {
+        self.outputs.log.is_some()
+    }
+
+    /// Starts searching the process's log with the current search's regex,
+    /// reaching output that's left the scrollback. `None` without a log or a regex.
+    pub(crate) fn search_log(&self, limit: usize) -> Option<BackgroundSearch> {
+        let paths = self.outputs.log.as_ref()?.lock().paths();
+        let regex = self.search.read().as_ref()?.regex()?.clone();
+        Some(BackgroundSearch::start(paths, regex, limit))
+    }
+
+    pub(crate) fn end_search(&self) {
+        self.search.write().take();
+        self.scroll_to_bottom();
+    }
+
+    /// Shows only the lines matching `query`, as described by [`Filter`], or
+    /// the whole screen again without one.
+    pub(crate) fn set_filter(&self, query: Option<&str>) {
+        *self.filter.write() = query.map(Filter::new);
+    }
+
+    pub(crate) fn filter_query(&self) -> Option<String> {
+        self.filter
+            .read()
+            .as_ref()
+            .map(|filter| filter.query().to_owned())
+    }
+
+    /// Lines matching an error highlight rule since the pane was last focused.
+    pub(crate) fn error_count(&self) -> usize {
+        self.activity.errors()
+    }
+
+    /// Whether there's been output since the pane was last focused.
+    pub(crate) fn has_unread(&self) -> bool {
+        self.activity.unread()
+    }
+
+    /// Clears the activity markers, now that the pane's been looked at.
+    pub(crate) fn mark_seen(&self) {
+        self.activity.seen();
+    }
+
+    pub(crate) fn search_summary(&self) -> Option<String> {
+        self.search.read().as_ref().map(Search::summary)
+    }
+
+    /// Scrolls so that `found` sits in the middle of the screen, where possible.
+    fn reveal(parser: &mut vt100::Parser, found: Option<Match>) {
+        let Some(line) = found.and_then(|found| found.line.checked_sub(history::dropped(parser)))
+        else {
+            return;
+        };
+        let len = history_len(parser);
+        let half = usize::from(parser.screen().size().0 / 2);
+        parser.set_scrollback((len + half).saturating_sub(line));
+    }
+
+    /// Enters copy mode at the child's cursor, or at the bottom of the view when
+    /// it's scrolled back.
+    pub(crate) fn start_copy(&self) {
+        let mut parser = self.parser.write();
+        let len = history_len(&mut parser);
+        let screen = parser.screen();
+        let cursor = if screen.scrollback() == 0 {
+            let (row, col) = screen.cursor_position();
+            Point {
+                line: len + usize::from(row),
+                col,
+            }
+        } else {
+            let (rows, _) = screen.size();
+            Point {
+                line: len - screen.scrollback() + usize::from(rows).saturating_sub(1),
+                col: 0,
+            }
+        };
+        *self.copy.write() = Some(CopyMode::new(cursor));
+    }
+
+    /// Enters copy mode at a position on the screen, as a mouse press does.
+    pub(crate) fn start_copy_at(&self, row: u16, col: u16) {
+        let point = self.point_at(row, col);
+        *self.copy.write() = Some(CopyMode::new(point));
+    }
+
+    /// Extends the copy mode selection to a position on the screen.
+    pub(crate) fn drag_select(&self, row: u16, col: u16) {
+        let point = self.point_at(row, col);
+        if let Some(copy) = self.copy.write().as_mut() {
+            copy.drag_to(point);
+        }
+    }
+
+    pub(crate) fn copy_motion(&self, motion: Motion) {
+        let mut parser = self.parser.write();
+        let mut copy = self.copy.write();
+        let Some(copy) = copy.as_mut() else {
+            return;
+        };
+        let lines = history(&mut parser);
+        let (rows, cols) = parser.screen().size();
+        copy.motion(motion, &lines, usize::from(rows), cols);
+        Self::follow(&mut parser, copy.cursor);
+    }
+
+    pub(crate) fn copy_toggle_selection(&self, linewise: bool) {
+        if let Some(copy) = self.copy.write().as_mut() {
+            copy.toggle_selection(linewise);
+        }
+    }
+
+    /// Leaves copy mode, returning the selected text if anything was selected.
+    pub(crate) fn end_copy(&self) -> Option<String> {
+        let copy = self.copy.write().take()?;
+        if !copy.has_selection() {
+            return None;
+        }
+        let lines = history(&mut self.parser.write());
+        Some(copy.selected_text(&lines))
+    }
+
+    /// The point in the history shown at a position on the screen.
+    fn point_at(&self, row: u16, col: u16) -> Point {
+        let mut parser = self.parser.write();
+        let len = history_len(&mut parser);
+        Point {
+            line: len - parser.screen().scrollback() + usize::from(row),
+            col,
+        }
+    }
+
+    /// Scrolls just enough to bring `point` into view.
+    fn follow(parser: &mut vt100::Parser, point: Point) {
+        let len = history_len(parser);
+        let rows = usize::from(parser.screen().size().0);
+        let top = len - parser.screen().scrollback();
+        if point.line < top {
+            parser.set_scrollback(len - point.line);
+        } else if point.line >= top + rows {
+            parser.set_scrollback((len + rows).saturating_sub(point.line + 1));
+        }
+    }
+
+    /// Sends a key press to the child, encoded for its current cursor key mode.
+    pub(crate) fn handle_key(&self, key: KeyEvent) {
+        let application_cursor = self.parser.read().screen().application_cursor();
+        if let Some(bytes) = encode_key(key, application_cursor) {
+            self.handle_input(Bytes::from(bytes));
+        }
+    }
+
+    /// Sends pasted text to the child, bracketed if it has asked for that so it
+    /// can tell a paste from typing.
+    pub(crate) fn paste(&self, text: &str) {
+        let bracketed = self.parser.read().screen().bracketed_paste();
+        self.handle_input(Bytes::from(paste_input(text, bracketed)));
+    }
+
+    /// Encodes a mouse event for the child, if it has asked for mouse reporting.
+    pub(crate) fn encode_mouse(&self, event: MouseEvent) -> Option<Vec<u8>> {
+        let parser = self.parser.read();
+        let screen = parser.screen();
+        encode_mouse(
+            event,
+            screen.mouse_protocol_mode(),
+            screen.mouse_protocol_encoding(),
+        )
+    }
+
+    /// Subscribes to the child's raw output. Also returns the escape sequences
+    /// that redraw the screen as it is now, which the output carries on from.
+    pub(crate) fn subscribe(&self) -> (Vec<u8>, broadcast::Receiver<Bytes>) {
+        // The reader sends output while holding the lock, so none is missed or repeated.
+        let parser = self.parser.read();
+        let mut snapshot = b"\x1b[H\x1b[2J".to_vec();
+        snapshot.extend(parser.screen().contents_formatted());
+        (snapshot, self.output.subscribe())
+    }
+
     pub(crate) fn handle_input(&self, input: Bytes) {
+        self.scroll_to_bottom();
         let sender = self.sender.clone();
         tokio::spawn(async move { sender.clone().unwrap().send(input).await });
     }
 
-    fn command_runner(cmd: CommandBuilder, pty: Box<dyn SlavePty + Send>) {
-        let mut child = pty.spawn_command(cmd).unwrap();
-        let _child_exit_status = child.wait().unwrap();
+    fn command_runner(
+        mut child: Box<dyn Child + Send + Sync>,
+        pty: Box<dyn SlavePty + Send>,
+        status: &RwLock<ProcessStatus>,
+        exited: &RwLock<Option<Instant>>,
+        ending: &RwLock<Option<Ending>>,
+        name: &str,
+        events: &EventBus,
+    ) {
+        let (exit_status, signal) = Self::wait(child.as_mut());
+        *exited.write() = Some(Instant::now());
+        let ending = *ending.read();
+        let new_status = match ending {
+            // It was stopped on purpose, so keep it looking that way.
+            Some(Ending::Stop) => {
+                events.publish(&Event::Stopped {
+                    process: name.to_owned(),
+                });
+                ProcessStatus::Stopped
+            }
+            Some(Ending::OverLimit(limit)) => ProcessStatus::over_limit(limit),
+            Some(Ending::Restart) | None => ProcessStatus::exited(exit_status, signal),
+        };
+        if let Some(exit_status) = new_status.exit_status() {
+            events.publish(&Event::Exited {
+                process: name.to_owned(),
+                code: exit_status.exit_code(),
+                status: exit_status.to_string(),
+            });
+        }
+        *status.write() = new_status;
         drop(pty);
     }
 
-    async fn output_writer(
-        mut rx: Receiver<Bytes>,
-        mut writer: BufWriter<Box<dyn Write + Send>>,
-        pty: Box<dyn MasterPty + Send>,
-    ) {
+    /// Waits for the child to exit, returning how it did along with the signal
+    /// that killed it, which portable-pty only reports as text.
+    fn wait(child: &mut (dyn Child + Send + Sync)) -> (ExitStatus, Option<Signal>) {
+        let pid = child
+            .process_id()
+            .and_then(|pid| i32::try_from(pid).ok())
+            .map(Pid::from_raw);
+        loop {
+            match pid.map(|pid| waitpid(pid, None)) {
+                Some(Ok(WaitStatus::Exited(_, code))) => {
+                    let code = u32::try_from(code).unwrap_or_default();
+                    return (ExitStatus::with_exit_code(code), None);
+                }
+                Some(Ok(WaitStatus::Signaled(_, signal, _))) => {
+                    return (ExitStatus::with_signal(signal.as_str()), Some(signal));
+                }
+                Some(Ok(_) | Err(Errno::EINTR)) => {}
+                // Without a pid to wait on, there's no signal to find either.
+                Some(Err(_)) | None => {
+                    let status = child
+                        .wait()
+                        .unwrap_or_else(|_| ExitStatus::with_exit_code(1));
+                    return (status, None);
+                }
+            }
+        }
+    }
+
+    async fn output_writer(mut rx: Receiver<Bytes>, mut writer: BufWriter<Box<dyn Write + Send>>) {
         while let Some(bytes) = rx.recv().await {
             if let Err(e) = writer.write_all(&bytes) {
                 println!("error writing to writer: {:?}", e);
@@ -91,35 +731,74 @@ impl ProcessScreen {
                 break;
             }
         }
-        drop(pty);
     }
 
-    async fn output_reader(mut reader: Box<dyn Read + Send>, parser: Arc<RwLock<vt100::Parser>>) {
+    fn output_reader(
+        mut reader: Box<dyn Read + Send>,
+        live: &RwLock<bool>,
+        parser: &RwLock<vt100::Parser>,
+        output: &broadcast::Sender<Bytes>,
+        sink: &mut OutputSink,
+    ) {
         // Consume the output from the child
         // Can't read the full buffer, since that would wait for EOF
         let mut buf = [0u8; 8192];
-        let mut processed_buf = Vec::new();
         loop {
-            let size = reader.read(&mut buf).unwrap();
-            if size <= 0 {
+            let size = match reader.read(&mut buf) {
+                Ok(0) | Err(_) => break,
+                Ok(size) => size,
+            };
+            let live = live.read();
+            if !*live {
                 break;
             }
-            processed_buf.extend_from_slice(&buf[..size]);
-            parser.write().process(&processed_buf);
-
-            // Clear the processed portion of the buffer
-            processed_buf.clear();
+            let mut parser = parser.write();
+            parser.process(&buf[..size]);
+            // Sending fails when nobody's attached, which is fine.
+            let _ = output.send(Bytes::copy_from_slice(&buf[..size]));
+            drop(parser);
+            sink.write(&buf[..size]);
         }
     }
 }
 
 impl Drop for ProcessScreen {
     fn drop(&mut self) {
-        if let Some(tasks) = self.tasks.take() {
-            for task in tasks.iter() {
-                task.abort();
-            }
-        }
-        self.sender.take();
+        self.stop_tasks();
+    }
+}
+
+/// What to send the child for pasted `text`, between paste markers if it's
+/// `bracketed`.
+fn paste_input(text: &str, bracketed: bool) -> String {
+    if bracketed {
+        // Dropping any end marker in the text stops it from ending the paste early.
+        format!(
+            "{}{}{}",
+            PASTE_START,
+            text.replace(PASTE_END, ""),
+            PASTE_END
+        )
+    } else {
+        text.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn brackets_pastes_when_asked() {
+        assert_eq!("ls\n", paste_input("ls\n", false));
+        assert_eq!("\x1b[200~ls\n\x1b[201~", paste_input("ls\n", true));
+    }
+
+    #[test]
+    fn keeps_pasted_end_markers_from_ending_the_paste() {
+        assert_eq!(
+            "\x1b[200~echo rm -rf\x1b[201~",
+            paste_input("echo \x1b[201~rm -rf", true)
+        );
     }
 }
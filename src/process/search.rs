@@ -0,0 +1,185 @@
+use regex::Regex;
+
+use super::history::Line;
+
+/// A match in a process's history. `line` counts from the first line the
+/// screen ever had, so it doesn't move as old lines drop out of the scrollback.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct Match {
+    pub(crate) line: usize,
+    pub(crate) start: u16,
+    pub(crate) end: u16,
+}
+
+/// The state of a search over the focused process's history.
+#[derive(Debug, Default)]
+pub(crate) struct Search {
+    regex: Option<Regex>,
+    error: Option<String>,
+    /// The history searched, kept so that editing the query doesn't rebuild it.
+    lines: Vec<Line>,
+    /// The number of the first line in `lines`.
+    first: usize,
+    matches: Vec<Match>,
+    current: Option<usize>,
+}
+
+impl Search {
+    /// A search of `lines`, the first of which is line number `first`.
+    pub(crate) fn new(lines: Vec<Line>, first: usize) -> Self {
+        Self {
+            lines,
+            first,
+            ..Self::default()
+        }
+    }
+
+    /// Replaces the history searched, as when there's been more output.
+    pub(crate) fn load(&mut self, lines: Vec<Line>, first: usize) {
+        self.lines = lines;
+        self.first = first;
+    }
+
+    pub(crate) fn set_query(&mut self, query: &str) {
+        self.error = None;
+        self.regex = None;
+        if query.is_empty() {
+            return;
+        }
+        match Regex::new(query) {
+            Ok(regex) => self.regex = Some(regex),
+            Err(e) => self.error = Some(e.to_string()),
+        }
+    }
+
+    /// Finds every match in the history, keeping the current match where it
+    /// was or, for a fresh search, starting at the most recent one.
+    pub(crate) fn refresh(&mut self) {
+        let previous = self.current_match();
+        self.matches.clear();
+        if let Some(regex) = &self.regex {
+            for (i, line) in self.lines.iter().enumerate() {
+                for m in regex.find_iter(line.text()).filter(|m| !m.is_empty()) {
+                    self.matches.push(Match {
+                        line: self.first + i,
+                        start: line.column(m.start()),
+                        end: line.column(m.end()),
+                    });
+                }
+            }
+        }
+        self.current = match previous {
+            Some(previous) => self
+                .matches
+                .iter()
+                .position(|m| *m >= previous)
+                .or_else(|| self.matches.len().checked_sub(1)),
+            None => self.matches.len().checked_sub(1),
+        };
+    }
+
+    /// Moves to the next match further down, or the previous one when `forward` is false.
+    pub(crate) fn step(&mut self, forward: bool) {
+        let len = self.matches.len();
+        if len == 0 {
+            return;
+        }
+        self.current = Some(match (self.current, forward) {
+            (Some(i), true) => (i + 1) % len,
+            (Some(i), false) => (i + len - 1) % len,
+            (None, _) => len - 1,
+        });
+    }
+
+    pub(crate) fn regex(&self) -> Option<&Regex> {
+        self.regex.as_ref()
+    }
+
+    pub(crate) fn current_match(&self) -> Option<Match> {
+        self.current.and_then(|i| self.matches.get(i)).copied()
+    }
+
+    pub(crate) fn matches(&self) -> &[Match] {
+        &self.matches
+    }
+
+    /// A short description of the search for the footer.
+    pub(crate) fn summary(&self) -> String {
+        if let Some(error) = &self.error {
+            return format!(
+                "invalid regex: {}",
+                error.lines().last().unwrap_or_default()
+            );
+        }
+        match self.current {
+            Some(i) => format!("{}/{}", i + 1, self.matches.len()),
+            None if self.regex.is_some() => "no matches".to_owned(),
+            None => String::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::history::{dropped, history, tests::parser_with_lines};
+
+    #[test]
+    fn starts_at_most_recent_match() {
+        let mut parser = parser_with_lines(10);
+        let mut search = Search::new(history(&mut parser), 0);
+        search.set_query(r"line [0-9]$");
+        search.refresh();
+
+        assert_eq!("9/9", search.summary());
+        assert_eq!(
+            Some(Match {
+                line: 8,
+                start: 0,
+                end: 6
+            }),
+            search.current_match()
+        );
+
+        search.step(true);
+        assert_eq!("1/9", search.summary());
+        search.step(false);
+        assert_eq!("9/9", search.summary());
+    }
+
+    #[test]
+    fn reports_invalid_regex() {
+        let mut search = Search::default();
+        search.set_query("(unclosed");
+        search.refresh();
+
+        assert!(search.summary().starts_with("invalid regex"));
+        assert_eq!(None, search.current_match());
+    }
+
+    #[test]
+    fn anchors_matches_as_lines_drop_out() {
+        // 4 rows and 6 lines of scrollback, so 10 lines in all.
+        let mut parser = vt100::Parser::new(4, 20, 6);
+        for i in 1..=8 {
+            parser.process(format!("line {}\r\n", i).as_bytes());
+        }
+        let mut search = Search::new(history(&mut parser), dropped(&parser));
+        search.set_query("line 5");
+        search.refresh();
+        let found = search.current_match();
+        assert_eq!(Some(4), found.map(|m| m.line));
+
+        for i in 9..=12 {
+            parser.process(format!("line {}\r\n", i).as_bytes());
+        }
+        assert_eq!(3, dropped(&parser));
+        search.load(history(&mut parser), dropped(&parser));
+        search.refresh();
+        assert_eq!(found, search.current_match());
+        assert_eq!(
+            "line 5",
+            search.lines[found.unwrap().line - search.first].text()
+        );
+    }
+}
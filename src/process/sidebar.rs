@@ -0,0 +1,156 @@
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Paragraph},
+};
+
+use super::{bar::markers, merged::color, screen::ProcessScreen, status::format_duration};
+use crate::list::List;
+
+/// Columns the sidebar takes, borders included.
+pub(crate) const WIDTH: u16 = 30;
+/// Rows each process gets in the sidebar.
+const ENTRY_HEIGHT: u16 = 4;
+/// CPU samples shown in an entry's sparkline.
+const SPARKLINE_WIDTH: usize = 8;
+
+/// Lists every process with its state, pid, uptime, restarts, readiness and
+/// resource use.
+pub(crate) struct Sidebar<'a> {
+    blocks: &'a List<ProcessScreen>,
+    sparkline: bool,
+}
+
+impl<'a> Sidebar<'a> {
+    pub(crate) fn new(blocks: &'a List<ProcessScreen>) -> Self {
+        Self {
+            blocks,
+            sparkline: false,
+        }
+    }
+
+    /// Shows each process's recent CPU use beside its current use.
+    pub(crate) fn sparkline(mut self, sparkline: bool) -> Self {
+        self.sparkline = sparkline;
+        self
+    }
+
+    /// The process whose entry is on `row` of a sidebar rendered into `area`
+    /// with the `focused` process in view.
+    pub(crate) fn entry_at(area: Rect, focused: usize, row: u16) -> Option<usize> {
+        let inner = Self::block().inner(area);
+        (inner.top()..inner.bottom()).contains(&row).then(|| {
+            Self::first_shown(inner, focused) + usize::from((row - inner.top()) / ENTRY_HEIGHT)
+        })
+    }
+
+    /// The first entry shown, scrolling just far enough to show the focused one.
+    fn first_shown(inner: Rect, focused: usize) -> usize {
+        let fits = usize::from(inner.height / ENTRY_HEIGHT).max(1);
+        (focused + 1).saturating_sub(fits)
+    }
+
+    fn block() -> Block<'static> {
+        Block::default().borders(Borders::ALL).title(" processes ")
+    }
+
+    fn entry(&self, index: usize, block: &ProcessScreen, focused: bool) -> Vec<Line<'static>> {
+        let style = if focused {
+            Style::default()
+                .fg(Color::Black)
+                .bg(color(index))
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+                .fg(color(index))
+                .add_modifier(Modifier::BOLD)
+        };
+        let mut name = vec![Span::styled(
+            format!(" {} {} ", index + 1, block.name),
+            style,
+        )];
+        if !focused {
+            name.extend(markers(block));
+        }
+
+        let status = block.status();
+        let state_style = Style::default().fg(status.color());
+        let mut state = vec![Span::raw("   "), Span::styled(status.state(), state_style)];
+        if let Some(code) = status.exit_status().map(|status| status.exit_code()) {
+            state.push(Span::styled(format!(" ({})", code), state_style));
+        }
+        if let Some(pid) = block.pid() {
+            state.push(Span::raw(format!("  pid {}", pid)));
+        }
+
+        let mut details = vec![Span::raw("   ")];
+        if let Some(uptime) = block.uptime() {
+            details.push(Span::raw(format!("up {}  ", format_duration(uptime))));
+        }
+        if block.restarts() > 0 {
+            details.push(Span::raw(format!("↻ {}  ", block.restarts())));
+        }
+        match block.readiness() {
+            _ if block.unhealthy() => {
+                details.push(Span::styled("unhealthy", Style::default().fg(Color::Red)));
+            }
+            Some(true) => details.push(Span::styled("ready", Style::default().fg(Color::Green))),
+            Some(false) => {
+                details.push(Span::styled(
+                    "not ready",
+                    Style::default().fg(Color::Yellow),
+                ));
+            }
+            None => {}
+        }
+
+        let mut usage = String::from("   ");
+        if let Some(current) = block.usage() {
+            usage.push_str(&current.summary());
+            if self.sparkline {
+                usage.push_str(&format!(" {}", block.usage.sparkline(SPARKLINE_WIDTH)));
+            }
+        }
+
+        vec![
+            Line::from(name),
+            Line::from(state),
+            Line::from(details),
+            Line::from(usage),
+        ]
+    }
+}
+
+impl<'a> Widget for Sidebar<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer)
+    where
+        Self: Sized,
+    {
+        let focused = self.blocks.focused_index();
+        let block = Self::block();
+        let first = Self::first_shown(block.inner(area), focused);
+        let lines: Vec<Line> = self
+            .blocks
+            .iter()
+            .enumerate()
+            .skip(first)
+            .flat_map(|(i, block)| self.entry(i, block, i == focused))
+            .collect();
+        Paragraph::new(lines).block(block).render(area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_the_entry_on_a_row() {
+        // Three entries fit between the borders, on rows 2 to 13.
+        let area = Rect::new(0, 1, 30, 14);
+        let entries =
+            |focused| [1, 2, 5, 6, 13, 14].map(|row| Sidebar::entry_at(area, focused, row));
+        assert_eq!([None, Some(0), Some(0), Some(1), Some(2), None], entries(0));
+        // Scrolled just far enough to show the focused entry at the bottom.
+        assert_eq!([None, Some(2), Some(2), Some(3), Some(4), None], entries(4));
+    }
+}
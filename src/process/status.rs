@@ -0,0 +1,260 @@
+use std::{fmt, time::Duration};
+
+use nix::sys::signal::Signal;
+use portable_pty::ExitStatus;
+use ratatui::style::Color;
+
+use super::usage::format_bytes;
+
+/// The lifecycle state of a child process.
+#[derive(Debug, Clone, Default)]
+pub(crate) enum ProcessStatus {
+    #[default]
+    Running,
+    /// Exited by itself, or was killed by the signal.
+    Exited(ExitStatus, Option<Signal>),
+    /// Killed for going over one of its resource limits.
+    OverLimit(Limit, ExitStatus),
+    /// Stopped on request, which isn't treated as the process exiting by itself.
+    Stopped,
+}
+
+impl ProcessStatus {
+    /// The status for a process that exited with `status`, or was killed by
+    /// `signal`. The kernel sends SIGXCPU once it's over its CPU time limit.
+    pub(crate) fn exited(status: ExitStatus, signal: Option<Signal>) -> Self {
+        if signal == Some(Signal::SIGXCPU) {
+            Self::over_limit(Limit::Cpu)
+        } else {
+            ProcessStatus::Exited(status, signal)
+        }
+    }
+
+    /// The status for a process killed for going over `limit`, which is what
+    /// its exit status describes.
+    pub(crate) fn over_limit(limit: Limit) -> Self {
+        ProcessStatus::OverLimit(limit, ExitStatus::with_signal(&limit.to_string()))
+    }
+
+    pub(crate) fn exit_status(&self) -> Option<&ExitStatus> {
+        match self {
+            ProcessStatus::Running | ProcessStatus::Stopped => None,
+            ProcessStatus::Exited(status, _) | ProcessStatus::OverLimit(_, status) => Some(status),
+        }
+    }
+
+    pub(crate) fn is_running(&self) -> bool {
+        matches!(self, ProcessStatus::Running)
+    }
+
+    /// A one-word description: running, exited, killed or stopped.
+    pub(crate) fn state(&self) -> &'static str {
+        match self {
+            ProcessStatus::Running => "running",
+            ProcessStatus::Exited(..) => "exited",
+            ProcessStatus::OverLimit(..) => "killed",
+            ProcessStatus::Stopped => "stopped",
+        }
+    }
+
+    /// Green while running, red after failing and gray otherwise.
+    pub(crate) fn color(&self) -> Color {
+        if self.is_running() {
+            Color::Green
+        } else if self.failed() {
+            Color::Red
+        } else {
+            Color::DarkGray
+        }
+    }
+
+    pub(crate) fn has_exited(&self) -> bool {
+        self.exit_status().is_some()
+    }
+
+    pub(crate) fn failed(&self) -> bool {
+        self.exit_status().is_some_and(|status| !status.success())
+    }
+
+    /// The exit code truncated to what a process can report to its parent,
+    /// or 128 plus the signal that killed it, as shells report it.
+    pub(crate) fn exit_code(&self) -> Option<u8> {
+        let signal = match self {
+            ProcessStatus::Exited(_, signal) => *signal,
+            ProcessStatus::OverLimit(Limit::Memory(_), _) => Some(Signal::SIGKILL),
+            ProcessStatus::OverLimit(Limit::Cpu, _) => Some(Signal::SIGXCPU),
+            ProcessStatus::Running | ProcessStatus::Stopped => None,
+        };
+        signal.map_or_else(
+            || {
+                self.exit_status()
+                    .map(|status| u8::try_from(status.exit_code()).unwrap_or(u8::MAX))
+            },
+            |signal| u8::try_from(128 + signal as i32).ok(),
+        )
+    }
+}
+
+/// A resource limit a process was killed for going over.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Limit {
+    /// Resident memory, in bytes.
+    Memory(u64),
+    Cpu,
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Limit::Memory(bytes) => write!(f, "memory limit of {} exceeded", format_bytes(*bytes)),
+            Limit::Cpu => f.write_str("CPU time limit exceeded"),
+        }
+    }
+}
+
+/// A short, rough duration like `45s`, `3m12s`, `2h05m` or `3d04h`.
+pub(crate) fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m{:02}s", secs / 60, secs % 60),
+        3600..=86_399 => format!("{}h{:02}m", secs / 3600, secs / 60 % 60),
+        _ => format!("{}d{:02}h", secs / 86_400, secs / 3600 % 24),
+    }
+}
+
+/// Conditions under which hawk stops on its own instead of waiting for the user.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ExitPolicy {
+    /// Stop as soon as any process exits.
+    pub(crate) abort_on_exit: bool,
+    /// Stop when the named process exits, propagating its exit code.
+    pub(crate) exit_with: Option<String>,
+}
+
+impl ExitPolicy {
+    /// Returns the code hawk should exit with if it's time to stop, given each
+    /// process's name and status.
+    pub(crate) fn check(&self, processes: &[(String, ProcessStatus)]) -> Option<u8> {
+        if let Some(name) = &self.exit_with {
+            let code = processes
+                .iter()
+                .find(|(process, _)| process == name)
+                .and_then(|(_, status)| status.exit_code());
+            if code.is_some() {
+                return code;
+            }
+        }
+        let statuses = processes.iter().map(|(_, status)| status);
+        if self.abort_on_exit && statuses.clone().any(ProcessStatus::has_exited) {
+            return Some(exit_code(statuses));
+        }
+        None
+    }
+}
+
+/// The code hawk exits with once it stops: nonzero if any process failed.
+pub(crate) fn exit_code<'a>(mut statuses: impl Iterator<Item = &'a ProcessStatus>) -> u8 {
+    u8::from(statuses.any(ProcessStatus::failed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exited(code: u32) -> ProcessStatus {
+        ProcessStatus::Exited(ExitStatus::with_exit_code(code), None)
+    }
+
+    fn processes(statuses: [ProcessStatus; 2]) -> Vec<(String, ProcessStatus)> {
+        ["web".to_owned(), "worker".to_owned()]
+            .into_iter()
+            .zip(statuses)
+            .collect()
+    }
+
+    #[test]
+    fn truncates_exit_codes() {
+        assert_eq!(exited(3).exit_code(), Some(3));
+        assert_eq!(exited(300).exit_code(), Some(u8::MAX));
+        assert_eq!(ProcessStatus::Running.exit_code(), None);
+        assert_eq!(ProcessStatus::Stopped.exit_code(), None);
+    }
+
+    #[test]
+    fn reports_signals_like_a_shell() {
+        let killed = ProcessStatus::exited(
+            ExitStatus::with_signal(Signal::SIGKILL.as_str()),
+            Some(Signal::SIGKILL),
+        );
+        assert_eq!(killed.exit_code(), Some(137));
+        let hung_up = ProcessStatus::exited(
+            ExitStatus::with_signal(Signal::SIGHUP.as_str()),
+            Some(Signal::SIGHUP),
+        );
+        assert_eq!(hung_up.exit_code(), Some(129));
+        assert_eq!(
+            ProcessStatus::over_limit(Limit::Memory(1024)).exit_code(),
+            Some(137)
+        );
+        assert_eq!(ProcessStatus::over_limit(Limit::Cpu).exit_code(), Some(152));
+    }
+
+    #[test]
+    fn spots_cpu_limit_kills() {
+        let killed = |signal: Signal| {
+            ProcessStatus::exited(ExitStatus::with_signal(signal.as_str()), Some(signal))
+        };
+        assert!(matches!(
+            killed(Signal::SIGXCPU),
+            ProcessStatus::OverLimit(Limit::Cpu, _)
+        ));
+        assert!(matches!(killed(Signal::SIGKILL), ProcessStatus::Exited(..)));
+        assert!(matches!(
+            ProcessStatus::exited(ExitStatus::with_exit_code(0), None),
+            ProcessStatus::Exited(..)
+        ));
+    }
+
+    #[test]
+    fn exits_with_failures() {
+        let statuses = [exited(0), ProcessStatus::Running];
+        assert_eq!(exit_code(statuses.iter()), 0);
+        let statuses = [exited(0), exited(2), ProcessStatus::Stopped];
+        assert_eq!(exit_code(statuses.iter()), 1);
+    }
+
+    #[test]
+    fn keeps_running_by_default() {
+        let policy = ExitPolicy::default();
+        assert_eq!(policy.check(&processes([exited(1), exited(0)])), None);
+    }
+
+    #[test]
+    fn aborts_on_any_exit() {
+        let policy = ExitPolicy {
+            abort_on_exit: true,
+            exit_with: None,
+        };
+        let running = processes([ProcessStatus::Running, ProcessStatus::Stopped]);
+        assert_eq!(policy.check(&running), None);
+        let succeeded = processes([exited(0), ProcessStatus::Running]);
+        assert_eq!(policy.check(&succeeded), Some(0));
+        let failed = processes([ProcessStatus::Running, exited(7)]);
+        assert_eq!(policy.check(&failed), Some(1));
+    }
+
+    #[test]
+    fn exits_with_a_process() {
+        let policy = ExitPolicy {
+            abort_on_exit: false,
+            exit_with: Some("worker".to_owned()),
+        };
+        let others = processes([exited(4), ProcessStatus::Running]);
+        assert_eq!(policy.check(&others), None);
+        let worker = processes([ProcessStatus::Running, exited(4)]);
+        assert_eq!(policy.check(&worker), Some(4));
+        let stopped = processes([ProcessStatus::Running, ProcessStatus::Stopped]);
+        assert_eq!(policy.check(&stopped), None);
+    }
+}
@@ -0,0 +1,183 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fs,
+    sync::Arc,
+    time::Instant,
+};
+
+use parking_lot::RwLock;
+
+/// Clock ticks per second that `/proc` counts CPU time in. It's 100 on every
+/// Linux we run on, and finding out for sure would need `sysconf`.
+const TICKS_PER_SECOND: f64 = 100.0;
+/// Samples kept for the sparkline.
+const HISTORY: usize = 30;
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// A process tree's CPU and memory use.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub(crate) struct Usage {
+    /// Percent of one core used since the previous sample.
+    pub(crate) cpu: f64,
+    /// Resident memory in bytes.
+    pub(crate) rss: u64,
+}
+
+impl Usage {
+    /// Like `12.5% 340M`.
+    pub(crate) fn summary(&self) -> String {
+        format!("{:.1}% {}", self.cpu, format_bytes(self.rss))
+    }
+}
+
+/// The last few samples of a process's usage.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct UsageHistory {
+    samples: Arc<RwLock<VecDeque<Usage>>>,
+}
+
+impl UsageHistory {
+    fn push(&self, usage: Usage) {
+        let mut samples = self.samples.write();
+        if samples.len() == HISTORY {
+            samples.pop_front();
+        }
+        samples.push_back(usage);
+    }
+
+    pub(crate) fn clear(&self) {
+        self.samples.write().clear();
+    }
+
+    pub(crate) fn latest(&self) -> Option<Usage> {
+        self.samples.read().back().copied()
+    }
+
+    /// The CPU history as a line of bars, the newest on the right and at most
+    /// `width` of them.
+    pub(crate) fn sparkline(&self, width: usize) -> String {
+        let samples = self.samples.read();
+        let cpu: Vec<f64> = samples
+            .iter()
+            .skip(samples.len().saturating_sub(width))
+            .map(|usage| usage.cpu)
+            .collect();
+        let max = cpu.iter().copied().fold(100.0, f64::max);
+        cpu.iter()
+            .map(|cpu| SPARKS[((cpu / max) * 7.0).round().clamp(0.0, 7.0) as usize])
+            .collect()
+    }
+}
+
+/// Samples the usage of process trees from `/proc`.
+#[derive(Debug, Default)]
+pub(crate) struct UsageSampler {
+    /// CPU ticks used by each process at the last sample.
+    ticks: HashMap<u32, u64>,
+    sampled: Option<Instant>,
+}
+
+impl UsageSampler {
+    /// Adds a sample of each running process and its descendants to its history.
+    pub(crate) fn sample(&mut self, processes: &[(Option<u32>, UsageHistory)]) {
+        let now = Instant::now();
+        let elapsed = self
+            .sampled
+            .map_or(0.0, |sampled| (now - sampled).as_secs_f64());
+        let mut children: HashMap<u32, Vec<u32>> = HashMap::new();
+        let mut ticks = HashMap::new();
+        for pid in all_pids() {
+            if let Some((parent, used)) = read_stat(pid) {
+                children.entry(parent).or_default().push(pid);
+                ticks.insert(pid, used);
+            }
+        }
+
+        for (root, history) in processes {
+            let Some(root) = root.filter(|pid| ticks.contains_key(pid)) else {
+                continue;
+            };
+            let mut usage = Usage::default();
+            let mut used = 0;
+            let mut pending = vec![root];
+            while let Some(pid) = pending.pop() {
+                let previous = self.ticks.get(&pid).copied().unwrap_or_default();
+                used += ticks[&pid].saturating_sub(previous);
+                usage.rss += read_rss(pid).unwrap_or_default();
+                pending.extend(children.get(&pid).into_iter().flatten());
+            }
+            if elapsed > 0.0 {
+                usage.cpu = used as f64 / TICKS_PER_SECOND / elapsed * 100.0;
+            }
+            history.push(usage);
+        }
+        self.ticks = ticks;
+        self.sampled = Some(now);
+    }
+}
+
+fn all_pids() -> impl Iterator<Item = u32> {
+    fs::read_dir("/proc")
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse().ok())
+}
+
+/// The parent and CPU ticks of `pid`, from `/proc/<pid>/stat`.
+fn read_stat(pid: u32) -> Option<(u32, u64)> {
+    parse_stat(&fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?)
+}
+
+fn parse_stat(stat: &str) -> Option<(u32, u64)> {
+    // The command name is in parentheses and may contain anything, so the
+    // fields are counted from the last closing one, starting with the state.
+    let (_, fields) = stat.rsplit_once(')')?;
+    let fields: Vec<&str> = fields.split_whitespace().collect();
+    let parent = fields.get(1)?.parse().ok()?;
+    let user: u64 = fields.get(11)?.parse().ok()?;
+    let system: u64 = fields.get(12)?.parse().ok()?;
+    Some((parent, user + system))
+}
+
+/// The resident memory of `pid` in bytes, from `/proc/<pid>/status`.
+fn read_rss(pid: u32) -> Option<u64> {
+    parse_rss(&fs::read_to_string(format!("/proc/{}/status", pid)).ok()?)
+}
+
+fn parse_rss(status: &str) -> Option<u64> {
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    let kilobytes: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kilobytes * 1024)
+}
+
+/// A short size like `512K`, `45.6M` or `4.1G`.
+pub(crate) fn format_bytes(bytes: u64) -> String {
+    const UNIT: f64 = 1024.0;
+    let kilobytes = bytes as f64 / UNIT;
+    if kilobytes < UNIT {
+        format!("{:.0}K", kilobytes)
+    } else if kilobytes < UNIT * UNIT {
+        format!("{:.1}M", kilobytes / UNIT)
+    } else {
+        format!("{:.1}G", kilobytes / UNIT / UNIT)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_proc_files() {
+        let stat = "42 (a (weird) name) S 7 42 42 0 -1 4194304 100 0 0 0 150 25 0 0 20 0 1 0";
+        assert_eq!(parse_stat(stat), Some((7, 175)));
+        assert_eq!(
+            parse_rss("Name:\tx\nVmRSS:\t  2048 kB\n"),
+            Some(2048 * 1024)
+        );
+        assert_eq!(parse_rss("Name:\tkthreadd\n"), None);
+        assert_eq!(format_bytes(512 * 1024), "512K");
+        assert_eq!(format_bytes(45 * 1024 * 1024 + 600 * 1024), "45.6M");
+        assert_eq!(format_bytes(4 * 1024 * 1024 * 1024), "4.0G");
+    }
+}
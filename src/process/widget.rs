@@ -5,15 +5,36 @@ use ratatui::{
     prelude::*,
     widgets::{Block, Borders, Paragraph},
 };
-use tui_term::widget::PseudoTerminal;
+use tui_term::widget::{Cursor, PseudoTerminal};
 
-use super::screen::ProcessScreen;
+use crate::config::HighlightRule;
+
+use super::{
+    bar::UNREAD,
+    copy::{CopyMode, Point},
+    filter::Filter,
+    history::{self, history_len},
+    screen::ProcessScreen,
+    search::Search,
+    status::ProcessStatus,
+};
+
+/// CPU samples shown in a pane's header sparkline.
+const SPARKLINE_WIDTH: usize = 20;
 
 #[derive(Clone)]
 pub(crate) struct ProcessWidget<'a> {
-    name: &'a str,
+    process: &'a ProcessScreen,
     sized: bool,
+    focused: bool,
+    broadcast: bool,
+    status: ProcessStatus,
     pub(crate) parser: Arc<RwLock<vt100::Parser>>,
+    search: Arc<RwLock<Option<Search>>>,
+    copy: Arc<RwLock<Option<CopyMode>>>,
+    filter: Option<Filter>,
+    highlights: &'a [HighlightRule],
+    sparkline: bool,
 }
 
 impl<'a> ProcessWidget<'a> {
@@ -21,13 +42,189 @@ impl<'a> ProcessWidget<'a> {
         let sized = process.sized;
         // the screen's parser gets resized on first render, so we modify our
         // screen to reflect that it's parser has been (or will imminently be) resized.
-        (*process).sized = true;
+        process.sized = true;
         Self {
-            name: &process.name,
+            process,
             sized,
+            focused: false,
+            broadcast: false,
+            status: process.status(),
             parser: process.parser.clone(),
+            search: process.search.clone(),
+            copy: process.copy.clone(),
+            filter: process.filter.read().clone(),
+            highlights: &[],
+            sparkline: false,
         }
     }
+
+    pub(crate) fn focused(mut self, focused: bool) -> Self {
+        self.focused = focused;
+        self
+    }
+
+    /// Styles output matching these rules.
+    pub(crate) fn highlights(mut self, highlights: &'a [HighlightRule]) -> Self {
+        self.highlights = highlights;
+        self
+    }
+
+    /// Marks the pane as receiving broadcast input.
+    pub(crate) fn broadcast(mut self, broadcast: bool) -> Self {
+        self.broadcast = broadcast;
+        self
+    }
+
+    /// Shows the recent CPU use beside the current use in the header.
+    pub(crate) fn sparkline(mut self, sparkline: bool) -> Self {
+        self.sparkline = sparkline;
+        self
+    }
+
+    /// The part of a pane rendered into `area` that shows the child's screen.
+    pub(crate) fn screen_area(area: Rect) -> Rect {
+        let [_, main] = Self::split(area);
+        main.inner(Margin::new(1, 1))
+    }
+
+    /// The history line shown in the first row of the screen, and the screen's size.
+    fn view(&self) -> (usize, u16, u16) {
+        let mut parser = self.parser.write();
+        let len = history_len(&mut parser);
+        let screen = parser.screen();
+        let (rows, cols) = screen.size();
+        (len - screen.scrollback(), rows, cols)
+    }
+
+    /// Highlights the search matches that are currently on screen.
+    fn render_matches(&self, search: &Search, screen: Rect, buf: &mut Buffer) {
+        let (top, rows, _) = self.view();
+        // Matches are numbered from the first line the screen ever had.
+        let top = top + history::dropped(&self.parser.read());
+        let current = search.current_match();
+        for found in search.matches() {
+            let Some(row) = found
+                .line
+                .checked_sub(top)
+                .and_then(|row| u16::try_from(row).ok())
+                .filter(|row| *row < rows)
+            else {
+                continue;
+            };
+            let style = if Some(*found) == current {
+                Style::default().fg(Color::Black).bg(Color::LightRed)
+            } else {
+                Style::default().fg(Color::Black).bg(Color::Yellow)
+            };
+            let highlight = Rect::new(
+                screen.x.saturating_add(found.start),
+                screen.y.saturating_add(row),
+                found.end.saturating_sub(found.start),
+                1,
+            );
+            buf.set_style(highlight.intersection(screen), style);
+        }
+    }
+
+    /// Highlights the copy mode selection and cursor.
+    fn render_copy(&self, copy: &CopyMode, screen: Rect, buf: &mut Buffer) {
+        let (top, rows, cols) = self.view();
+        let selected = Style::default().fg(Color::White).bg(Color::Blue);
+        for row in 0..rows.min(screen.height) {
+            for col in 0..cols.min(screen.width) {
+                let point = Point {
+                    line: top + usize::from(row),
+                    col,
+                };
+                let style = if point == copy.cursor {
+                    Style::default().fg(Color::Black).bg(Color::LightCyan)
+                } else if copy.is_selected(point, cols) {
+                    selected
+                } else {
+                    continue;
+                };
+                buf[(screen.x + col, screen.y + row)].set_style(style);
+            }
+        }
+    }
+
+    /// The pane header: the process's name and state, and badges for anything
+    /// worth knowing about its view.
+    fn title(&self, scrollback: usize, filtered: Option<(&Filter, usize)>) -> Line<'static> {
+        let mut title = match &self.status {
+            ProcessStatus::Running => self.process.name.clone(),
+            ProcessStatus::Exited(status, _) => format!("{} ({})", self.process.name, status),
+            ProcessStatus::OverLimit(limit, _) => format!("{} ({})", self.process.name, limit),
+            ProcessStatus::Stopped => format!("{} (stopped)", self.process.name),
+        };
+        if self.process.marked {
+            title.push_str(" [marked]");
+        }
+        if let Some((filter, total)) = filtered {
+            let lines = if total == 1 { "line" } else { "lines" };
+            title.push_str(&format!(" [{}, {} {}]", filter.summary(), total, lines));
+        } else if scrollback > 0 {
+            title.push_str(&format!(" [scrolled {} lines]", scrollback));
+        }
+        let mut title = vec![Span::raw(title)];
+        if let Some(usage) = self.process.usage() {
+            let mut text = format!("  {}", usage.summary());
+            if self.sparkline {
+                text.push_str(&format!(
+                    " {}",
+                    self.process.usage.sparkline(SPARKLINE_WIDTH)
+                ));
+            }
+            title.push(Span::styled(text, Style::default().fg(Color::DarkGray)));
+        }
+        if self.process.has_unread() {
+            title.insert(
+                0,
+                Span::styled(format!("{} ", UNREAD), Style::default().fg(Color::Yellow)),
+            );
+        }
+        let errors = self.process.error_count();
+        if errors > 0 {
+            let label = if errors == 1 { "error" } else { "errors" };
+            title.push(Span::raw(" "));
+            title.push(Span::styled(
+                format!(" {} {} ", errors, label),
+                Style::default().fg(Color::White).bg(Color::Red),
+            ));
+        }
+        Line::from(title)
+    }
+
+    /// Styles the text on screen that matches a highlight rule.
+    fn render_highlights(&self, screen: Rect, buf: &mut Buffer) {
+        if self.highlights.is_empty() {
+            return;
+        }
+        for y in screen.top()..screen.bottom() {
+            // The row's text, and the column each of its bytes is in.
+            let mut text = String::new();
+            let mut columns = Vec::new();
+            for x in screen.left()..screen.right() {
+                let symbol = buf[(x, y)].symbol();
+                text.push_str(symbol);
+                columns.extend(std::iter::repeat(x).take(symbol.len()));
+            }
+            for rule in self.highlights {
+                for found in rule.pattern.find_iter(&text).filter(|m| !m.is_empty()) {
+                    let start = columns[found.start()];
+                    let end = columns[found.end() - 1] + 1;
+                    buf.set_style(Rect::new(start, y, end - start, 1), rule.style);
+                }
+            }
+        }
+    }
+
+    fn split(area: Rect) -> [Rect; 2] {
+        Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Fill(1)].as_ref())
+            .areas(area)
+    }
 }
 
 impl<'a> Widget for ProcessWidget<'a> {
@@ -35,28 +232,67 @@ impl<'a> Widget for ProcessWidget<'a> {
     where
         Self: Sized,
     {
-        let [header, main] = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([Constraint::Length(1), Constraint::Fill(1)].as_ref())
-            .areas(area);
+        let [header, main] = Self::split(area);
 
-        let p = Paragraph::new(self.name)
+        let scrollback = self.parser.read().screen().scrollback();
+        let height = usize::from(Self::screen_area(area).height);
+        let filtered = self
+            .filter
+            .as_ref()
+            .map(|filter| (filter, self.process.lines.filtered(filter, height)));
+        let title = self.title(
+            scrollback,
+            filtered
+                .as_ref()
+                .map(|(filter, (_, total))| (*filter, *total)),
+        );
+        Paragraph::new(title)
             .style(Style::default().add_modifier(Modifier::BOLD))
-            .alignment(Alignment::Center);
-        p.render(header, buf);
+            .alignment(Alignment::Center)
+            .render(header, buf);
 
+        let border_style = if self.broadcast {
+            Style::default().fg(Color::LightRed)
+        } else if self.focused {
+            Style::default().fg(Color::Cyan)
+        } else {
+            Style::default()
+        };
         let block = Block::default()
             .borders(Borders::ALL)
+            .border_style(border_style)
             .style(Style::default().add_modifier(Modifier::BOLD));
 
         if !self.sized {
-            self.parser.write().set_size(main.height, main.width);
+            let screen = Self::screen_area(area);
+            self.process.resize(screen.height, screen.width);
+        }
+
+        let screen = Self::screen_area(area);
+        if let Some((_, (lines, _))) = filtered {
+            let lines: Vec<Line> = lines.into_iter().map(Line::from).collect();
+            Paragraph::new(lines).block(block).render(main, buf);
+            self.render_highlights(screen, buf);
+            return;
         }
 
         let parser = self.parser.read();
-        let screen = parser.screen();
-        let pseudo_term = PseudoTerminal::new(screen).block(block);
+        // The child's cursor is on the live screen, not somewhere in the history.
+        let copying = self.copy.read().is_some();
+        let cursor = Cursor::default().visibility(scrollback == 0 && !copying);
+        let pseudo_term = PseudoTerminal::new(parser.screen())
+            .block(block)
+            .cursor(cursor);
 
         pseudo_term.render(main, buf);
+        drop(parser);
+
+        self.render_highlights(screen, buf);
+        if let Some(search) = self.search.read().as_ref() {
+            self.render_matches(search, screen, buf);
+        }
+        if let Some(copy) = self.copy.read().as_ref() {
+            self.render_copy(copy, screen, buf);
+        }
     }
 }